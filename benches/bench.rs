@@ -3,11 +3,13 @@ mod word_builder;
 use crate::word_builder::generate_test_strings;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use std::sync::Arc;
+#[cfg(feature = "global")]
+use sto::{repository, Sto};
 use sto::{Repository, ScopedSto};
 
 fn bench_create(c: &mut Criterion) {
     c.bench_function("create_repository", |b| {
-        b.iter_with_large_drop(|| Repository::new())
+        b.iter_with_large_drop(Repository::new)
     });
 }
 
@@ -93,6 +95,34 @@ fn bench_multi_thread(c: &mut Criterion) {
         });
     });
 
+    // compare with `--features rwlock`, where hits only take shared locks
+    THREADS.iter().for_each(|&threads| {
+        let repo = Repository::new();
+        for s in inputs.iter() {
+            ScopedSto::intern_in(s, &repo);
+        }
+        bg.bench_function(format!("lookup_overlapped_{threads}"), |b| {
+            b.iter(|| {
+                let repo_ref = &repo;
+                std::thread::scope(|scope| {
+                    for t in 0..threads {
+                        let inputs = inputs.clone();
+                        scope.spawn(move || {
+                            for s in inputs
+                                .iter()
+                                .cycle()
+                                .skip(t * 1000)
+                                .take(TOTAL_STRINGS / threads)
+                            {
+                                black_box(ScopedSto::intern_in(s, repo_ref));
+                            }
+                        });
+                    }
+                });
+            })
+        });
+    });
+
     THREADS.iter().for_each(|&threads| {
         bg.bench_function(format!("insert_overlapped_{threads}"), |b| {
             b.iter_with_large_drop(|| {
@@ -119,7 +149,42 @@ fn bench_multi_thread(c: &mut Criterion) {
     });
 }
 
+#[cfg(feature = "global")]
+fn bench_tokenizer(c: &mut Criterion) {
+    // punctuation and single characters interleaved with short words,
+    // like what a tokenizer usually produces
+    let words = generate_test_strings(1_000, 4);
+    let tokens = words
+        .iter()
+        .flat_map(|word| [word.as_str(), " ", ",", "a", ""])
+        .collect::<Vec<_>>();
+
+    let mut bg = c.benchmark_group("tokenizer");
+
+    bg.bench_function("global_fast_path", |b| {
+        b.iter(|| {
+            tokens.iter().for_each(|token| {
+                black_box(Sto::from(token));
+            });
+        })
+    });
+
+    bg.bench_function("global_bucket_path", |b| {
+        b.iter(|| {
+            tokens.iter().for_each(|token| {
+                black_box(ScopedSto::intern_in(token, repository()));
+            });
+        })
+    });
+}
+
 criterion_group!(create, bench_create);
 criterion_group!(single_thread, bench_single_thread);
 criterion_group!(multi_thread, bench_multi_thread);
+#[cfg(feature = "global")]
+criterion_group!(tokenizer, bench_tokenizer);
+
+#[cfg(feature = "global")]
+criterion_main!(create, single_thread, multi_thread, tokenizer);
+#[cfg(not(feature = "global"))]
 criterion_main!(create, single_thread, multi_thread);
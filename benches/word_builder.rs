@@ -1,4 +1,4 @@
-/// Copied from https://github.com/Robbepop/string-interner/blob/90805f/benches/setup.rs#L14
+// Copied from https://github.com/Robbepop/string-interner/blob/90805f/benches/setup.rs#L14
 
 /// Alphabet containing all characters that may be put into a benchmark string.
 const ALPHABET: [u8; 64] = [
@@ -0,0 +1,31 @@
+use crate::Sto;
+use alloc::string::String;
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+/// Takes a string from the unstructured data and interns it in the global
+/// [Repository](crate::Repository).
+///
+/// Fuzz targets can take [Sto] inputs directly, which exercises interning with
+/// adversarial strings, including empty and very long ones.
+///
+/// ## Example
+/// ```
+/// # use arbitrary::{Arbitrary, Unstructured};
+/// # use sto::Sto;
+/// let sto = Sto::arbitrary_take_rest(Unstructured::new(b"hello")).unwrap();
+/// assert_eq!(sto, Sto::from("hello"));
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "arbitrary")))]
+impl<'a> Arbitrary<'a> for Sto {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        String::arbitrary(u).map(Sto::from)
+    }
+
+    fn arbitrary_take_rest(u: Unstructured<'a>) -> Result<Self> {
+        String::arbitrary_take_rest(u).map(Sto::from)
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        String::size_hint(depth)
+    }
+}
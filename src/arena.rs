@@ -1,68 +1,278 @@
-use crate::constants::{ALLOC_ALIGNMENT, CHUNK_DEFAULT_CAPACITY, CHUNK_USABLE_THRESHOLD};
-use std::alloc::{alloc, dealloc, Layout};
-use std::cell::Cell;
-use std::mem::size_of;
-use std::ptr::{copy_nonoverlapping, eq, write, NonNull};
+use crate::builder::Config;
+use crate::constants::{ALLOC_ALIGNMENT, CHUNK_USABLE_THRESHOLD};
+use crate::entry::INDEXED;
+use crate::error::InternError;
+use alloc::alloc::{alloc, dealloc, Layout};
+use core::cell::Cell;
+use core::mem::size_of;
+use core::ptr::{eq, write, NonNull};
+#[cfg(feature = "refcount")]
+use {crate::entry::Entry, alloc::vec::Vec};
+
+/// The size of the header before the chars of a string:
+/// the reference count with the feature `refcount`, the hash and the length.
+pub(crate) const HEADER_SIZE: usize = REFCOUNT_SIZE + size_of::<u64>() + size_of::<usize>();
+#[cfg(feature = "refcount")]
+const REFCOUNT_SIZE: usize = size_of::<usize>();
+#[cfg(not(feature = "refcount"))]
+const REFCOUNT_SIZE: usize = 0;
+
+/// The size of the slot of the id before the header of a string,
+/// only allocated by a repository with [RepositoryBuilder::indexed](crate::RepositoryBuilder::indexed).
+const ID_SIZE: usize = size_of::<usize>();
+
+/// The size of the header before the chars of a string, including the slot of its id if any.
+#[inline]
+const fn header_size(config: &Config) -> usize {
+    if config.indexed {
+        HEADER_SIZE + ID_SIZE
+    } else {
+        HEADER_SIZE
+    }
+}
+
+/// The minimum capacity of a chunk, which holds the chunk itself and the header of an empty string.
+pub(crate) const MIN_CHUNK_CAPACITY: usize = size_of::<Chunk>() + HEADER_SIZE;
+
+/// The number of released slots looked at for a new string, from the latest one.
+#[cfg(feature = "refcount")]
+const RECYCLE_SCAN_LIMIT: usize = 16;
 
 pub(crate) struct Arena {
     chunk: Cell<NonNull<Chunk>>,
+    /// the total size of owned chunks
+    allocated: usize,
+    /// slots of removed strings, which can be reused by new strings no longer than them
+    #[cfg(feature = "refcount")]
+    recycled: Vec<Entry>,
 }
 
 impl Arena {
     pub(crate) fn new() -> Self {
         Self {
             chunk: Cell::new(DUMMY_CHUNK.get()),
+            allocated: 0,
+            #[cfg(feature = "refcount")]
+            recycled: Vec::new(),
+        }
+    }
+
+    /// Constructs an [Arena] whose first chunk lives in `buf`.
+    ///
+    /// Falls back to [Arena::new] if `buf` is too small to hold a chunk.
+    pub(crate) fn with_buffer(buf: &'static mut [u8]) -> Self {
+        let start = buf.as_mut_ptr() as usize;
+        let low = match round_up(start, ALLOC_ALIGNMENT) {
+            Some(low) if low - start <= buf.len() => low,
+            _ => return Self::new(),
+        };
+        let size = round_down(buf.len() - (low - start), ALLOC_ALIGNMENT);
+        if size < size_of::<Chunk>() + CHUNK_USABLE_THRESHOLD {
+            return Self::new();
+        }
+
+        let low = unsafe { buf.as_mut_ptr().add(low - start) };
+        let chunk = unsafe { Chunk::init(low, size, DUMMY_CHUNK.get(), false) };
+        Self {
+            chunk: Cell::new(chunk),
+            allocated: 0,
+            #[cfg(feature = "refcount")]
+            recycled: Vec::new(),
         }
     }
 
-    pub(crate) fn alloc_str(&mut self, hash: u64, string: &str) -> NonNull<u8> {
-        let str_len = string.len();
-        let char_ptr = string.as_ptr();
-        if let Some(ptr) = unsafe { self.try_alloc_str_fast_path(hash, str_len, char_ptr) } {
-            ptr
+    /// Allocates the space of a string of `str_len` bytes and writes its header.
+    ///
+    /// The chars are left uninitialized, and should be written through [Arena::chars_of]
+    /// before the entry is used.
+    pub(crate) fn try_alloc(
+        &mut self,
+        hash: u64,
+        str_len: usize,
+        config: &Config,
+    ) -> Result<NonNull<u8>, InternError> {
+        // the flag of an indexed entry takes a bit of the length
+        if str_len & INDEXED != 0 {
+            return Err(InternError::StringTooLarge);
+        }
+        #[cfg(feature = "refcount")]
+        if let Some(ptr) = self.try_alloc_recycled(hash, str_len, config) {
+            return Ok(ptr);
+        }
+        if let Some(ptr) = unsafe { self.try_alloc_fast_path(hash, str_len, config) } {
+            Ok(ptr)
         } else {
-            unsafe { self.try_alloc_str_slow_path(hash, str_len, char_ptr) }
+            unsafe { self.try_alloc_slow_path(hash, str_len, config) }
         }
     }
 
-    pub(crate) fn allocated_memory(&self) -> usize {
-        let mut size = 0;
+    /// Keeps the slot of a removed entry, to be reused by [Arena::try_alloc].
+    ///
+    /// # Safety
+    /// The entry must be allocated by this arena, and must not be used anymore.
+    #[cfg(feature = "refcount")]
+    pub(crate) unsafe fn recycle(&mut self, entry: Entry) {
+        self.recycled.push(entry);
+    }
+
+    #[cfg(feature = "refcount")]
+    fn try_alloc_recycled(
+        &mut self,
+        hash: u64,
+        str_len: usize,
+        config: &Config,
+    ) -> Option<NonNull<u8>> {
+        let index = self
+            .recycled
+            .iter()
+            .enumerate()
+            .rev()
+            .take(RECYCLE_SCAN_LIMIT)
+            .find(|(_, entry)| entry.inline_len() >= str_len)?
+            .0;
+        let entry = self.recycled.swap_remove(index);
+        // chars keep the alignment of the slot, so only the header and the sentinel are rewritten
+        unsafe {
+            Some(write_header(
+                Arena::chars_of(entry.0),
+                hash,
+                str_len,
+                config,
+            ))
+        }
+    }
+
+    /// Returns the pointer to the chars of an allocated entry.
+    #[inline]
+    pub(crate) fn chars_of(ptr: NonNull<u8>) -> *mut u8 {
+        unsafe { (ptr.as_ptr() as *mut usize).add(1) as *mut u8 }
+    }
+
+    /// Whether a string of `str_len` bytes is too large for a chunk of the configured capacity,
+    /// so a dedicated chunk would be allocated for it.
+    pub(crate) fn needs_dedicated_chunk(str_len: usize, config: &Config) -> bool {
+        match Chunk::needed_bytes_for_string(str_len, config) {
+            Some(needed_bytes) => Chunk::is_exceed_capacity(needed_bytes, config.chunk_capacity),
+            None => true,
+        }
+    }
+
+    /// Returns the chunk which contains `ptr`, walking from the latest chunk.
+    pub(crate) fn chunk_of(&self, ptr: *const u8) -> Option<ChunkId> {
+        let addr = ptr as usize;
         let mut chunk = self.chunk.get();
         unsafe {
             while !chunk.as_ref().is_dummy() {
-                size += chunk.as_ref().size;
+                let low = chunk.as_ref().low as usize;
+                if low <= addr && addr < low + chunk.as_ref().size {
+                    return Some(ChunkId(low));
+                }
                 chunk = chunk.as_ref().prev;
             }
-        };
-        size
+        }
+        None
+    }
+
+    /// Frees all chunks, so all allocated strings become invalid.
+    ///
+    /// A chunk over a caller-provided buffer is not freed, but emptied to be reused.
+    pub(crate) fn clear(&mut self) {
+        let mut borrowed = None;
+        let mut chunk = self.chunk.get();
+        unsafe {
+            while !chunk.as_ref().is_dummy() {
+                let prev = chunk.as_ref().prev;
+                if chunk.as_ref().owned {
+                    let layout =
+                        Layout::from_size_align_unchecked(chunk.as_ref().size, ALLOC_ALIGNMENT);
+                    dealloc(chunk.as_ref().low, layout);
+                } else {
+                    borrowed = Some(chunk);
+                }
+                chunk = prev;
+            }
+        }
+
+        match borrowed {
+            Some(mut chunk) => unsafe {
+                let chunk_ref = chunk.as_mut();
+                chunk_ref.prev = DUMMY_CHUNK.get();
+                chunk_ref.cur.set(chunk.as_ptr() as *mut u8);
+                chunk_ref.used.set(0);
+                self.chunk.set(chunk);
+            },
+            None => self.chunk.set(DUMMY_CHUNK.get()),
+        }
+        self.allocated = 0;
+        #[cfg(feature = "refcount")]
+        self.recycled.clear();
+    }
+
+    pub(crate) fn allocated_memory(&self) -> usize {
+        self.allocated
+    }
+
+    /// The number of chunks allocated by the arena.
+    pub(crate) fn chunk_count(&self) -> usize {
+        self.owned_chunk_sizes().count()
+    }
+
+    /// The size of the largest chunk allocated by the arena, or 0 if there is none.
+    pub(crate) fn largest_chunk(&self) -> usize {
+        self.owned_chunk_sizes().max().unwrap_or(0)
+    }
+
+    /// The bytes of allocated chunks which are not taken by the hash, length and chars of strings,
+    /// including chunk headers, alignment paddings, sentinels and unused tails of chunks.
+    pub(crate) fn wasted_bytes(&self) -> usize {
+        self.owned_chunks()
+            .map(|chunk| chunk.size - chunk.used.get())
+            .sum()
+    }
+
+    /// The sizes of chunks allocated by the arena, excluding a chunk over a caller-provided buffer.
+    fn owned_chunk_sizes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.owned_chunks().map(|chunk| chunk.size)
+    }
+
+    fn owned_chunks(&self) -> impl Iterator<Item = &Chunk> + '_ {
+        let mut chunk = self.chunk.get();
+        core::iter::from_fn(move || unsafe {
+            while !chunk.as_ref().is_dummy() {
+                let cur = chunk.as_ref();
+                chunk = cur.prev;
+                if cur.owned {
+                    return Some(cur);
+                }
+            }
+            None
+        })
     }
 }
 
 impl Arena {
     #[inline]
-    unsafe fn try_alloc_str_fast_path(
+    unsafe fn try_alloc_fast_path(
         &mut self,
         hash: u64,
         str_len: usize,
-        char_ptr: *const u8,
+        config: &Config,
     ) -> Option<NonNull<u8>> {
-        self.chunk
-            .get()
-            .as_ref()
-            .try_alloc_str(hash, str_len, char_ptr)
+        self.chunk.get().as_ref().try_alloc(hash, str_len, config)
     }
 
-    unsafe fn try_alloc_str_slow_path(
+    unsafe fn try_alloc_slow_path(
         &mut self,
         hash: u64,
         str_len: usize,
-        char_ptr: *const u8,
-    ) -> NonNull<u8> {
+        config: &Config,
+    ) -> Result<NonNull<u8>, InternError> {
         let cur_chunk = self.chunk.get();
 
-        let needed_bytes = Chunk::needed_bytes_for_string(str_len).expect("too large");
-        let new_chunk = if Chunk::is_exceed_default_capacity(needed_bytes) {
-            let chunk = Chunk::new_for_needed_bytes(cur_chunk, needed_bytes);
+        let needed_bytes =
+            Chunk::needed_bytes_for_string(str_len, config).ok_or(InternError::StringTooLarge)?;
+        let new_chunk = if Chunk::is_exceed_capacity(needed_bytes, config.chunk_capacity) {
+            let chunk = Chunk::new_for_needed_bytes(cur_chunk, needed_bytes)?;
             // after create a Chunk for a large string, should check if the prev one
             // is still usable
             if cur_chunk.as_ref().is_still_usable() {
@@ -72,15 +282,16 @@ impl Arena {
             }
             chunk
         } else {
-            let chunk = Chunk::new(cur_chunk);
+            let chunk = Chunk::new(cur_chunk, config.chunk_capacity)?;
             self.chunk.set(chunk);
             chunk
         };
 
-        new_chunk
+        self.allocated += new_chunk.as_ref().size;
+        Ok(new_chunk
             .as_ref()
-            .try_alloc_str(hash, str_len, char_ptr)
-            .expect("internal error")
+            .try_alloc(hash, str_len, config)
+            .expect("internal error"))
     }
 }
 
@@ -92,20 +303,24 @@ impl Default for Arena {
 
 impl Drop for Arena {
     fn drop(&mut self) {
-        let mut chunk = self.chunk.get();
-        unsafe {
-            while !chunk.as_ref().is_dummy() {
-                let layout =
-                    Layout::from_size_align_unchecked(chunk.as_ref().size, ALLOC_ALIGNMENT);
-                let prev = chunk.as_ref().prev;
-                dealloc(chunk.as_ref().low, layout);
-                chunk = prev;
-            }
-        }
+        self.clear()
     }
 }
 
 unsafe impl Send for Arena {}
+
+// the chunks are only modified through `&mut Arena`
+unsafe impl Sync for Arena {}
+
+/// Identifies the arena chunk an interned string is stored in.
+///
+/// Strings in the same chunk are close to each other in memory,
+/// so handles can be grouped by `ChunkId` to improve cache locality.
+///
+/// A `ChunkId` is only meaningful within the [Repository](crate::Repository) it comes from.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ChunkId(usize);
+
 struct Chunk {
     prev: NonNull<Chunk>,
     size: usize,
@@ -113,32 +328,39 @@ struct Chunk {
     cur: Cell<*mut u8>,
     /// the start of the allocated chunk
     low: *mut u8,
+    /// whether the memory is allocated by the chunk, or provided by the caller
+    owned: bool,
+    /// bytes taken by the hash, length and chars of allocated strings
+    used: Cell<usize>,
 }
 
 impl Chunk {
-    pub(crate) fn new(prev: NonNull<Chunk>) -> NonNull<Self> {
-        unsafe { Self::try_new_with_size(prev, CHUNK_DEFAULT_CAPACITY).expect("oom") }
+    pub(crate) fn new(prev: NonNull<Chunk>, capacity: usize) -> Result<NonNull<Self>, InternError> {
+        unsafe { Self::try_new_with_size(prev, capacity) }
     }
 
-    pub(crate) fn new_for_needed_bytes(prev: NonNull<Chunk>, bytes: usize) -> NonNull<Self> {
-        unsafe {
-            Self::try_new_with_size(
-                prev,
-                bytes.checked_add(size_of::<Chunk>()).expect("too large"),
-            )
-            .expect("oom")
-        }
+    pub(crate) fn new_for_needed_bytes(
+        prev: NonNull<Chunk>,
+        bytes: usize,
+    ) -> Result<NonNull<Self>, InternError> {
+        let size = bytes
+            .checked_add(size_of::<Chunk>())
+            .ok_or(InternError::StringTooLarge)?;
+        unsafe { Self::try_new_with_size(prev, size) }
     }
 }
 
 impl Chunk {
-    pub(crate) const fn is_exceed_default_capacity(needed_bytes: usize) -> bool {
-        needed_bytes > CHUNK_DEFAULT_CAPACITY - size_of::<Chunk>()
+    pub(crate) const fn is_exceed_capacity(needed_bytes: usize, capacity: usize) -> bool {
+        needed_bytes > capacity - size_of::<Chunk>()
     }
 
-    pub(crate) const fn needed_bytes_for_string(str_len: usize) -> Option<usize> {
-        // len + hash + chars
-        str_len.checked_add(size_of::<usize>() + size_of::<u64>())
+    pub(crate) fn needed_bytes_for_string(str_len: usize, config: &Config) -> Option<usize> {
+        // header + chars + sentinel, and the padding in the worst case to align chars
+        let sentinel_len = config.sentinel.is_some() as usize;
+        str_len.checked_add(
+            header_size(config) + sentinel_len + config.string_alignment - ALLOC_ALIGNMENT,
+        )
     }
 
     pub(crate) fn swap(mut first: NonNull<Chunk>, mut second: NonNull<Chunk>) {
@@ -159,62 +381,96 @@ impl Chunk {
     }
 
     #[inline]
-    unsafe fn try_new_with_size(prev: NonNull<Chunk>, size: usize) -> Option<NonNull<Self>> {
-        let size = round_up(size, ALLOC_ALIGNMENT).expect("too large");
-
-        let layout = Layout::from_size_align_unchecked(size, ALLOC_ALIGNMENT);
+    unsafe fn try_new_with_size(
+        prev: NonNull<Chunk>,
+        size: usize,
+    ) -> Result<NonNull<Self>, InternError> {
+        // a layout must not exceed `isize::MAX` bytes after rounding
+        let size = round_up(size, ALLOC_ALIGNMENT).ok_or(InternError::StringTooLarge)?;
+        let layout = Layout::from_size_align(size, ALLOC_ALIGNMENT)
+            .map_err(|_| InternError::StringTooLarge)?;
 
         let low = alloc(layout);
         if low.is_null() {
-            None
+            Err(InternError::AllocFailed)
         } else {
-            // every chunk holds itself in the tail of allocated memory so we can operate
-            // pointers of chunks instead of values
-            let high = low.add(size) as *mut Chunk;
-            let chunk_self_start = high.sub(1);
-            write(
-                chunk_self_start,
-                Chunk {
-                    prev,
-                    size,
-                    cur: Cell::new(chunk_self_start as *mut u8),
-                    low,
-                },
-            );
-            Some(NonNull::new_unchecked(chunk_self_start))
+            Ok(Self::init(low, size, prev, true))
         }
     }
 
-    unsafe fn try_alloc_str(
-        &self,
-        hash: u64,
-        str_len: usize,
-        src_char_ptr: *const u8,
-    ) -> Option<NonNull<u8>> {
-        let cur = self.cur.get() as usize;
-        let dest_char_ptr = round_down(
-            cur.checked_sub(str_len).expect("too large"),
-            ALLOC_ALIGNMENT,
+    /// Writes a chunk over the `size` bytes from `low`, which are aligned to `ALLOC_ALIGNMENT`.
+    unsafe fn init(low: *mut u8, size: usize, prev: NonNull<Chunk>, owned: bool) -> NonNull<Self> {
+        // every chunk holds itself in the tail of allocated memory so we can operate
+        // pointers of chunks instead of values
+        let high = low.add(size) as *mut Chunk;
+        let chunk_self_start = high.sub(1);
+        write(
+            chunk_self_start,
+            Chunk {
+                prev,
+                size,
+                cur: Cell::new(chunk_self_start as *mut u8),
+                low,
+                owned,
+                used: Cell::new(0),
+            },
         );
-        if dest_char_ptr < self.low as usize + size_of::<usize>() + size_of::<u64>() {
+        NonNull::new_unchecked(chunk_self_start)
+    }
+
+    /// Reserves the space of a string whose chars start at a multiple of the configured alignment,
+    /// and writes its header and the configured sentinel.
+    unsafe fn try_alloc(&self, hash: u64, str_len: usize, config: &Config) -> Option<NonNull<u8>> {
+        let cur = self.cur.get() as usize;
+        let reserved = str_len.checked_add(config.sentinel.is_some() as usize)?;
+        let dest_char_ptr = round_down(cur.checked_sub(reserved)?, config.string_alignment);
+        let header_size = header_size(config);
+        if dest_char_ptr < self.low as usize + header_size {
             None
         } else {
-            // copy chars
-            copy_nonoverlapping(src_char_ptr, dest_char_ptr as *mut u8, str_len);
+            let ptr = write_header(dest_char_ptr as *mut u8, hash, str_len, config);
+            self.cur
+                .set(ptr.as_ptr().sub(header_size - size_of::<usize>()));
+            self.used.set(self.used.get() + header_size + str_len);
+            Some(ptr)
+        }
+    }
+}
+
+/// Writes the header and the configured sentinel of a string whose chars start at `chars`,
+/// and returns the pointer to its length.
+#[inline]
+unsafe fn write_header(chars: *mut u8, hash: u64, str_len: usize, config: &Config) -> NonNull<u8> {
+    // write sentinel
+    if let Some(sentinel) = config.sentinel {
+        write(chars.add(str_len), sentinel);
+    }
 
-            // write length
-            let dest_len_start = (dest_char_ptr as *mut usize).sub(1);
-            write(dest_len_start, str_len);
+    // write length
+    let dest_len_start = (chars as *mut usize).sub(1);
+    if config.indexed {
+        write(dest_len_start, str_len | INDEXED);
+    } else {
+        write(dest_len_start, str_len);
+    }
 
-            // write hash
-            let dest_hash_start = (dest_len_start as *mut u64).sub(1);
-            write(dest_hash_start, hash);
+    // write hash
+    let dest_hash_start = (dest_len_start as *mut u64).sub(1);
+    write(dest_hash_start, hash);
 
-            self.cur.set(dest_hash_start as *mut u8);
+    // write reference count
+    #[cfg(feature = "refcount")]
+    write((dest_hash_start as *mut usize).sub(1), 0);
 
-            Some(NonNull::new_unchecked(dest_len_start as *mut u8))
-        }
+    // write no id, which is assigned after the allocation
+    if config.indexed {
+        write(
+            (dest_len_start as *mut u8).sub(HEADER_SIZE) as *mut usize,
+            0,
+        );
     }
+
+    NonNull::new_unchecked(dest_len_start as *mut u8)
 }
 
 #[repr(transparent)]
@@ -233,10 +489,12 @@ static DUMMY_CHUNK: DummyChunk = DummyChunk(Chunk {
     size: 0,
     cur: Cell::new(&DUMMY_CHUNK as *const DummyChunk as *mut u8),
     low: &DUMMY_CHUNK as *const DummyChunk as *mut u8,
+    owned: false,
+    used: Cell::new(0),
 });
 
 #[inline]
-const fn round_up(n: usize, alignment: usize) -> Option<usize> {
+pub(crate) const fn round_up(n: usize, alignment: usize) -> Option<usize> {
     debug_assert!(alignment > 0);
     debug_assert!(alignment.is_power_of_two());
     if let Some(added) = n.checked_add(alignment - 1) {
@@ -0,0 +1,218 @@
+use crate::arena::{round_up, MIN_CHUNK_CAPACITY};
+use crate::constants::{ALLOC_ALIGNMENT, BUCKET_NUMBER, CHUNK_DEFAULT_CAPACITY};
+use crate::entry::LoadFactor;
+use crate::Repository;
+use core::hash::BuildHasher;
+
+/// A builder used to configure a [Repository].
+///
+/// [Repository::new] is a shortcut of `RepositoryBuilder::new().build()`.
+///
+/// ## Example
+/// ```
+/// # use sto::RepositoryBuilder;
+/// let repository = RepositoryBuilder::new().max_probe_length(16).build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct RepositoryBuilder {
+    config: Config,
+}
+
+impl RepositoryBuilder {
+    /// Constructs a new [RepositoryBuilder] with the default configuration.
+    pub fn new() -> Self {
+        Self {
+            config: Config::default(),
+        }
+    }
+
+    /// Sets the maximum number of slots an insertion may probe before the hash table
+    /// of a bucket grows, even though the load factor has not been reached yet.
+    ///
+    /// The table normally grows once it is 3/4 full, see [RepositoryBuilder::load_factor]. Under clustered inputs, probe
+    /// chains may become long well before that, so this threshold bounds the worst-case
+    /// probe length at the cost of occasional early growth.
+    ///
+    /// To avoid unbounded growth under adversarial inputs, an early growth happens at
+    /// most once per insertion and only when the table is at least half of its load
+    /// factor limit. Lookups of existing strings never trigger it.
+    ///
+    /// By default, there is no limit.
+    pub fn max_probe_length(mut self, max_probe_length: usize) -> Self {
+        self.config.max_probe_length = max_probe_length;
+        self
+    }
+
+    /// Sets the alignment of the bytes of every interned string,
+    /// which is useful to scan interned strings with SIMD instructions.
+    ///
+    /// The alignment must be a power of two, and is at least the size of a pointer.
+    /// A larger alignment wastes more memory to pad strings.
+    ///
+    /// ## Panics
+    /// Panics if `alignment` is not a power of two.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::builder().string_alignment(32).build();
+    /// let s = ScopedSto::intern_in("hello", &repository);
+    /// assert_eq!(s.as_str().as_ptr() as usize % 32, 0);
+    /// ```
+    pub fn string_alignment(mut self, alignment: usize) -> Self {
+        assert!(
+            alignment.is_power_of_two(),
+            "alignment must be a power of two"
+        );
+        self.config.string_alignment = alignment.max(ALLOC_ALIGNMENT);
+        self
+    }
+
+    /// Writes `sentinel` after the bytes of every interned string,
+    /// so scanners can detect the end of a string without checking its length.
+    ///
+    /// Every interned string takes one more byte of memory.
+    /// [ScopedSto::as_str](crate::ScopedSto::as_str) still excludes the sentinel,
+    /// use [ScopedSto::as_str_with_sentinel](crate::ScopedSto::as_str_with_sentinel) to include it.
+    ///
+    /// By default, there is no sentinel.
+    pub fn sentinel(mut self, sentinel: u8) -> Self {
+        self.config.sentinel = Some(sentinel);
+        self
+    }
+
+    /// Sets the capacity in bytes of every arena chunk where interned strings are stored,
+    /// rounded up to the size of a pointer.
+    ///
+    /// A string which does not fit in a chunk of this capacity gets a dedicated chunk.
+    /// A larger capacity makes such slow allocations rarer when most strings are long,
+    /// while a smaller one wastes less memory when only a few strings are interned.
+    ///
+    /// By default, it is 8 KiB.
+    ///
+    /// ## Panics
+    /// Panics if `capacity` is too small to hold the header of a chunk and an empty string.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::builder().chunk_capacity(1 << 16).build();
+    /// ScopedSto::intern_in("hello", &repository);
+    /// assert_eq!(repository.memory_stats().arena_bytes, 1 << 16);
+    /// ```
+    pub fn chunk_capacity(mut self, capacity: usize) -> Self {
+        assert!(
+            capacity >= MIN_CHUNK_CAPACITY,
+            "chunk capacity must be at least {MIN_CHUNK_CAPACITY} bytes"
+        );
+        self.config.chunk_capacity = round_up(capacity, ALLOC_ALIGNMENT).expect("too large");
+        self
+    }
+
+    /// Sets the load factor `num / den` at which the hash table of a bucket grows.
+    ///
+    /// A lower load factor makes probe chains shorter at the cost of more memory
+    /// for the tables, while a higher one saves memory but makes lookups slower.
+    ///
+    /// By default, it is 3/4.
+    ///
+    /// ## Panics
+    /// Panics unless `0 < num < den`.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::builder().load_factor(1, 2).build();
+    /// ScopedSto::intern_in("hello", &repository);
+    /// assert!(repository.load_factor() <= 0.5);
+    /// ```
+    pub fn load_factor(mut self, num: u8, den: u8) -> Self {
+        self.config.load_factor = LoadFactor::new(num, den);
+        self
+    }
+
+    /// Assigns a dense `u32` id to every interned string, in the order they are interned,
+    /// starting from 0.
+    ///
+    /// Ids can be read by [ScopedSto::id](crate::ScopedSto::id), resolved by [Repository::resolve]
+    /// and exported by [Repository::export_table].
+    /// They are stable only within the lifetime of a single `Repository`:
+    /// [Repository::retain] reassigns the ids of the retained strings to keep them dense,
+    /// and a copy made by [Repository::clone_contents] assigns its own ids.
+    ///
+    /// Every interned string takes the memory of two more pointers, for its id and its entry
+    /// in the table of ids, and interning a new string takes one more lock.
+    ///
+    /// ## Panics
+    /// Interning panics once more than `u32::MAX + 1` strings are interned.
+    pub fn indexed(mut self) -> Self {
+        self.config.indexed = true;
+        self
+    }
+
+    /// Constructs the [Repository].
+    pub fn build(self) -> Repository {
+        Repository::with_config(self.config)
+    }
+
+    /// Constructs the [Repository] which hashes strings with `hasher`.
+    ///
+    /// See [Repository::with_hasher].
+    pub fn build_with_hasher<H: BuildHasher>(self, hasher: H) -> Repository<BUCKET_NUMBER, H> {
+        Repository::with_config_and_hasher(self.config, hasher)
+    }
+
+    /// Constructs the [Repository] with `BUCKETS` buckets which hashes strings with `hasher`.
+    ///
+    /// See [Repository::with_buckets].
+    ///
+    /// ## Panics
+    /// Panics if `BUCKETS` is not a power of two.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::Repository;
+    /// # use std::collections::hash_map::RandomState;
+    /// let repository = Repository::builder()
+    ///     .max_probe_length(16)
+    ///     .build_sharded::<256, _>(RandomState::new());
+    /// ```
+    pub fn build_sharded<const BUCKETS: usize, H: BuildHasher>(
+        self,
+        hasher: H,
+    ) -> Repository<BUCKETS, H> {
+        Repository::with_config_and_hasher(self.config, hasher)
+    }
+}
+
+impl Default for RepositoryBuilder {
+    /// See [RepositoryBuilder::new].
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Config {
+    pub(crate) max_probe_length: usize,
+    /// always a power of two and at least `ALLOC_ALIGNMENT`
+    pub(crate) string_alignment: usize,
+    pub(crate) sentinel: Option<u8>,
+    pub(crate) indexed: bool,
+    /// always a multiple of `ALLOC_ALIGNMENT` and at least `MIN_CHUNK_CAPACITY`
+    pub(crate) chunk_capacity: usize,
+    pub(crate) load_factor: LoadFactor,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_probe_length: usize::MAX,
+            string_alignment: ALLOC_ALIGNMENT,
+            sentinel: None,
+            indexed: false,
+            chunk_capacity: CHUNK_DEFAULT_CAPACITY,
+            load_factor: LoadFactor::default(),
+        }
+    }
+}
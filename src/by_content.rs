@@ -0,0 +1,85 @@
+use crate::ScopedSto;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+
+/// A wrapper of [ScopedSto] which hashes and compares the contents of the string,
+/// so it can key a `HashMap` looked up with `&str`.
+///
+/// `ScopedSto` hashes like `str` too, but compares handles by pointers, which only agrees
+/// with comparing strings for handles of the same [Repository](crate::Repository).
+/// `ByContent` also compares the strings of handles from different `Repository`s.
+///
+/// ## Example
+/// ```
+/// # use std::collections::HashMap;
+/// # use sto::{ByContent, Repository, ScopedSto};
+/// let repository = Repository::new();
+/// let mut map = HashMap::new();
+/// map.insert(ByContent(ScopedSto::intern_in("key", &repository)), 42);
+/// assert_eq!(map.get("key"), Some(&42));
+/// ```
+#[derive(Copy, Clone)]
+pub struct ByContent<'a>(pub ScopedSto<'a>);
+
+impl<'a> ByContent<'a> {
+    /// Returns the wrapped [ScopedSto].
+    pub fn into_inner(self) -> ScopedSto<'a> {
+        self.0
+    }
+}
+
+impl<'a> From<ScopedSto<'a>> for ByContent<'a> {
+    fn from(value: ScopedSto<'a>) -> Self {
+        Self(value)
+    }
+}
+
+impl Hash for ByContent<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.as_str().hash(state)
+    }
+}
+
+impl PartialEq for ByContent<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        // same handle, or equal strings from different repositories
+        self.0 == other.0 || self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Eq for ByContent<'_> {}
+
+impl PartialOrd for ByContent<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ByContent<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl Borrow<str> for ByContent<'_> {
+    fn borrow(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl<'a> Deref for ByContent<'a> {
+    type Target = ScopedSto<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl fmt::Debug for ByContent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
@@ -0,0 +1,122 @@
+use crate::entry::Entry;
+use crate::Repository;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::marker::PhantomData;
+use core::ops::Deref;
+
+/// Represents an interned byte string, which is not necessarily UTF-8.
+///
+/// It works like [ScopedSto](crate::ScopedSto), but derefs to `[u8]`.
+/// See [Repository::intern_bytes_in].
+///
+/// ## Compare
+/// Byte strings are stored apart from strings, so a `ScopedStoBytes` is never equal to
+/// the handle of a string with the same bytes.
+/// Just like `ScopedSto`, two `ScopedStoBytes` can be compared cheaply
+/// **only when they are stored in the same `Repository`**.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct ScopedStoBytes<'a> {
+    entry: Entry,
+    _phantom: PhantomData<&'a ()>,
+}
+
+/// Alias for `'static` [ScopedStoBytes].
+pub type StoBytes = ScopedStoBytes<'static>;
+
+impl<'a> ScopedStoBytes<'a> {
+    fn new(entry: Entry) -> Self {
+        Self {
+            entry,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// The interned bytes.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.entry.as_bytes()
+    }
+
+    /// The precomputed hash.
+    pub fn hash(&self) -> u64 {
+        self.entry.hash()
+    }
+
+    /// The length of the interned bytes.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+}
+
+unsafe impl Send for ScopedStoBytes<'_> {}
+
+unsafe impl Sync for ScopedStoBytes<'_> {}
+
+impl Hash for ScopedStoBytes<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash().hash(state)
+    }
+}
+
+impl PartialEq<&[u8]> for ScopedStoBytes<'_> {
+    fn eq(&self, other: &&[u8]) -> bool {
+        self.as_bytes() == *other
+    }
+}
+
+impl PartialOrd<Self> for ScopedStoBytes<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScopedStoBytes<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl<'a> AsRef<[u8]> for ScopedStoBytes<'a> {
+    fn as_ref(&self) -> &'a [u8] {
+        self.as_bytes()
+    }
+}
+
+impl<'a> Deref for ScopedStoBytes<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &'a Self::Target {
+        self.as_bytes()
+    }
+}
+
+impl fmt::Debug for ScopedStoBytes<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\"", self.as_bytes().escape_ascii())
+    }
+}
+
+impl<const BUCKETS: usize, H: BuildHasher> Repository<BUCKETS, H> {
+    /// Interns a byte string, which is not necessarily UTF-8, like a file path on Unix.
+    ///
+    /// Byte strings are hashed like strings, but stored apart from them in a single bucket,
+    /// so interning byte strings from many threads contends on one lock.
+    /// They are not visited by [Repository::iter], nor counted by [Repository::len].
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::Repository;
+    /// let repository = Repository::new();
+    /// let a = repository.intern_bytes_in(b"\xFFhello");
+    /// let b = repository.intern_bytes_in(b"\xFFhello");
+    /// assert_eq!(a, b);
+    /// assert_eq!(a.as_bytes(), b"\xFFhello");
+    /// ```
+    pub fn intern_bytes_in(&self, bytes: &[u8]) -> ScopedStoBytes<'_> {
+        let hash = self.hash_bytes(bytes);
+        let outcome = self.bytes.0.lock().get_or_insert(hash, bytes, &self.config);
+        ScopedStoBytes::new(outcome.entry)
+    }
+}
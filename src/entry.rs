@@ -1,96 +1,409 @@
-use crate::constants::{ALLOC_ALIGNMENT, ENTRIES_INITIAL_CAPACITY};
-use std::alloc::{alloc, dealloc, Layout};
-use std::ptr::NonNull;
-use std::{mem, ptr, slice};
+use crate::arena::HEADER_SIZE;
+use crate::builder::Config;
+use crate::constants::{ALLOC_ALIGNMENT, ENTRIES_INITIAL_CAPACITY, ENTRIES_MIN_CAPACITY};
+use crate::error::InternError;
+use alloc::alloc::{alloc, dealloc, Layout};
+#[cfg(feature = "refcount")]
+use alloc::vec::Vec;
+use core::ptr::NonNull;
+#[cfg(feature = "refcount")]
+use core::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use core::{mem, ptr, slice};
 
 /// | hash (u64) | len (usize) | chars (len) |
 ///              ^
 ///           pointer
+///
+/// or, if the chars are borrowed rather than copied,
+///
+/// | hash (u64) | len | BORROWED (usize) | pointer to chars (usize) |
+///
+/// With the feature `refcount`, the hash is preceded by the reference count (usize).
+/// If the entry is indexed, the header is preceded by its id plus 1 (usize), or 0 if unassigned.
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub(crate) struct Entry(pub(crate) NonNull<u8>);
 
+/// The highest bit of the length of an entry whose chars are borrowed,
+/// which is never set by the length of a string.
+const BORROWED: usize = 1 << (usize::BITS - 1);
+
+/// The second highest bit of the length of an entry allocated with the slot of its id.
+pub(crate) const INDEXED: usize = 1 << (usize::BITS - 2);
+
+/// The bits of the length which are flags rather than the length of the string.
+const FLAGS: usize = BORROWED | INDEXED;
+
 impl Entry {
-    pub(crate) fn as_str<'a>(&self) -> &'a str {
+    /// Turns an allocated entry of a pointer-sized string into one borrowing `chars`.
+    ///
+    /// # Safety
+    /// `ptr` must be allocated for a string of `size_of::<usize>()` bytes.
+    pub(crate) unsafe fn write_borrowed(ptr: NonNull<u8>, chars: &'static [u8]) {
+        let ptr = ptr.as_ptr() as *mut usize;
+        let indexed = ptr::read(ptr) & INDEXED;
+        ptr::write(ptr, chars.len() | BORROWED | indexed);
+        ptr::write(ptr.add(1) as *mut *const u8, chars.as_ptr());
+    }
+
+    /// The number of handles held by callers of interning methods,
+    /// which is only modified under the lock of the bucket, or atomically under a shared lock.
+    #[cfg(feature = "refcount")]
+    pub(crate) fn refcount(&self) -> &AtomicUsize {
+        unsafe { &*((self.0.as_ptr() as *const u64).sub(2) as *const AtomicUsize) }
+    }
+
+    #[cfg(feature = "refcount")]
+    pub(crate) fn is_live(&self) -> bool {
+        self.refcount().load(AtomicOrdering::Relaxed) > 0
+    }
+
+    /// The number of chars the slot of the entry can hold inline.
+    #[cfg(feature = "refcount")]
+    pub(crate) fn inline_len(&self) -> usize {
+        let len = unsafe { ptr::read(self.0.as_ptr() as *const usize) };
+        if len & BORROWED == 0 {
+            len & !FLAGS
+        } else {
+            mem::size_of::<usize>()
+        }
+    }
+
+    /// Reads the pointer to the chars and the length.
+    #[inline]
+    fn chars(&self) -> (*const u8, usize) {
         unsafe {
             let ptr = self.0.as_ptr() as *const usize;
-            let str_len = ptr::read(ptr);
-            let char_ptr = ptr.add(1) as *const u8;
+            let len = ptr::read(ptr);
+            if len & BORROWED == 0 {
+                (ptr.add(1) as *const u8, len & !FLAGS)
+            } else {
+                (ptr::read(ptr.add(1) as *const *const u8), len & !FLAGS)
+            }
+        }
+    }
+
+    /// The interned string.
+    ///
+    /// The entry must be interned from a `str`, rather than from bytes.
+    pub(crate) fn as_str<'a>(&self) -> &'a str {
+        unsafe {
             #[allow(clippy::transmute_bytes_to_str)]
-            mem::transmute(slice::from_raw_parts(char_ptr, str_len))
+            mem::transmute(self.as_bytes())
         }
     }
 
+    /// The interned bytes, without checking whether they are UTF-8.
+    pub(crate) fn as_bytes<'a>(&self) -> &'a [u8] {
+        let (char_ptr, str_len) = self.chars();
+        unsafe { slice::from_raw_parts(char_ptr, str_len) }
+    }
+
     pub(crate) fn hash(&self) -> u64 {
         unsafe {
             let ptr = self.0.as_ptr() as *const u64;
             ptr::read(ptr.sub(1))
         }
     }
+
+    /// The chars followed by the byte after them.
+    ///
+    /// # Safety
+    /// The entry must be allocated with a sentinel, so its chars are not borrowed.
+    pub(crate) unsafe fn as_bytes_with_sentinel<'a>(&self) -> &'a [u8] {
+        let ptr = self.0.as_ptr() as *const usize;
+        let str_len = ptr::read(ptr) & !FLAGS;
+        slice::from_raw_parts(ptr.add(1) as *const u8, str_len + 1)
+    }
+
+    /// The id of the entry, if it is allocated with the slot of an id which is assigned.
+    pub(crate) fn id(&self) -> Option<u32> {
+        unsafe {
+            let len = ptr::read(self.0.as_ptr() as *const usize);
+            if len & INDEXED == 0 {
+                return None;
+            }
+            let id = ptr::read(self.id_slot());
+            id.checked_sub(1).map(|id| id as u32)
+        }
+    }
+
+    /// Assigns the id of an entry allocated with the slot of an id.
+    ///
+    /// # Safety
+    /// The entry must be allocated by a repository with `config.indexed`,
+    /// and must not be read by other threads meanwhile.
+    pub(crate) unsafe fn set_id(&self, id: u32) {
+        debug_assert!(ptr::read(self.0.as_ptr() as *const usize) & INDEXED != 0);
+        ptr::write(self.id_slot(), id as usize + 1);
+    }
+
+    fn id_slot(&self) -> *mut usize {
+        unsafe { self.0.as_ptr().sub(HEADER_SIZE) as *mut usize }
+    }
+
+    /// Reads the hash, the length and the pointer to chars at once.
+    pub(crate) fn parts(&self) -> (u64, usize, *const u8) {
+        let (char_ptr, str_len) = self.chars();
+        (self.hash(), str_len, char_ptr)
+    }
 }
 
 unsafe impl Sync for Entry {}
 unsafe impl Send for Entry {}
 
+/// The result of [Entries::try_get_or_insert].
+#[derive(Copy, Clone)]
+pub(crate) struct Outcome {
+    pub(crate) entry: Entry,
+    /// whether the table grew during the call
+    pub(crate) grew: bool,
+    /// whether the entry is newly inserted during the call
+    pub(crate) inserted: bool,
+}
+
+/// The fraction of slots a table may fill before it grows, which is `num / den`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct LoadFactor {
+    num: u8,
+    den: u8,
+}
+
+impl LoadFactor {
+    /// ## Panics
+    /// Panics unless `0 < num < den`.
+    pub(crate) fn new(num: u8, den: u8) -> Self {
+        assert!(num > 0, "the load factor must be positive");
+        assert!(num < den, "the load factor must be less than 1");
+        Self { num, den }
+    }
+
+    /// The number of items a table of `capacity` slots may hold,
+    /// which is always less than `capacity`, so probing always ends at an empty slot.
+    #[inline]
+    const fn max_item_count(self, capacity: usize) -> usize {
+        let (num, den) = (self.num as usize, self.den as usize);
+        capacity / den * num + capacity % den * num / den
+    }
+}
+
+impl Default for LoadFactor {
+    fn default() -> Self {
+        Self { num: 3, den: 4 }
+    }
+}
+
 /// Entries holds the allocated entries in hashmap.
 pub(crate) struct Entries {
     data: NonNull<Option<Entry>>,
     /// bytes to the next growth
-    ///   = size * 3 / 4 - items_count
-    ///   = (mask + 1) / 4 * 3 - items_count
-    /// Note that size should always be 4n
+    ///   = max_item_count(size) - items_count
     growth_left: usize,
     ///   = size - 1
     mask: usize,
+    load_factor: LoadFactor,
+    /// the number of entries whose reference count dropped to 0, which are still in the table
+    #[cfg(feature = "refcount")]
+    dead: usize,
 }
 
 static DUMMY_ENTRY_SLOT: Option<Entry> = None;
 
 impl Entries {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(load_factor: LoadFactor) -> Self {
         Self {
             data: unsafe {
                 NonNull::new_unchecked(&DUMMY_ENTRY_SLOT as *const _ as *mut Option<Entry>)
             },
             growth_left: 0,
             mask: 0,
+            load_factor,
+            #[cfg(feature = "refcount")]
+            dead: 0,
         }
     }
 
-    pub(crate) fn get_or_insert<F>(
+    /// Returns the entry of `string`, or inserts the one made by `entry_factory`.
+    /// With the feature `refcount`, the reference count of the returned entry is incremented.
+    ///
+    /// Fails if the table can not grow or `entry_factory` fails, leaving the table unchanged
+    /// except for its capacity.
+    pub(crate) fn try_get_or_insert<F>(
         &mut self,
         hash: u64,
-        string: &str,
-        mut entry_factory: F,
-    ) -> Entry
+        string: &[u8],
+        config: &Config,
+        entry_factory: F,
+    ) -> Result<Outcome, InternError>
     where
-        F: FnMut() -> Entry,
+        F: FnOnce() -> Result<Entry, InternError>,
     {
+        let mut grew = false;
         if self.growth_left == 0 {
-            unsafe { self.grow() }
+            unsafe { self.grow()? }
+            grew = true;
         }
 
         debug_assert!(self.growth_left > 0);
 
-        let mut pos = self.mask & hash as usize;
-        let mut dist = 0;
-        let slot = loop {
-            match unsafe { &mut *self.data.as_ptr().add(pos) } {
-                Some(entry) => {
-                    if entry.hash() == hash && entry.as_str() == string {
-                        return *entry;
-                    }
-                    dist += 1;
-                    pos = (pos + dist) & self.mask;
+        let (mut pos, dist) = match self.probe(hash, string) {
+            Ok(entry) => {
+                #[cfg(feature = "refcount")]
+                if entry.refcount().fetch_add(1, AtomicOrdering::Relaxed) == 0 {
+                    self.revive();
                 }
-                slot => break slot,
+                return Ok(Outcome {
+                    entry,
+                    grew,
+                    inserted: false,
+                });
             }
+            Err(probed) => probed,
         };
 
-        let new_entry = entry_factory();
+        if dist > config.max_probe_length && !grew && self.allow_early_grow() {
+            // an early growth is optional, so the table is still usable if it fails
+            if unsafe { self.grow() }.is_ok() {
+                grew = true;
+                pos = self.probe_empty(hash);
+            }
+        }
+
+        let new_entry = entry_factory()?;
+        let slot = unsafe { &mut *self.data.as_ptr().add(pos) };
         *slot = Some(new_entry);
         self.growth_left -= 1;
+        #[cfg(feature = "refcount")]
+        new_entry.refcount().store(1, AtomicOrdering::Relaxed);
+
+        Ok(Outcome {
+            entry: new_entry,
+            grew,
+            inserted: true,
+        })
+    }
+
+    /// Grows the table if needed, so `additional` more entries can be inserted without growing.
+    ///
+    /// The capacity is the smallest power of two holding all entries, so a few entries
+    /// do not allocate a table of the initial capacity.
+    pub(crate) fn reserve(&mut self, additional: usize) -> Result<(), InternError> {
+        if additional <= self.growth_left {
+            return Ok(());
+        }
+        let items_count = self
+            .len()
+            .checked_add(additional)
+            .ok_or(InternError::AllocFailed)?;
+        let mut capacity = self.capacity().max(ENTRIES_MIN_CAPACITY);
+        while self.max_item_count(capacity) < items_count {
+            capacity = capacity.checked_mul(2).ok_or(InternError::AllocFailed)?;
+        }
+        if capacity > isize::MAX as usize / mem::size_of::<Option<Entry>>() {
+            return Err(InternError::AllocFailed);
+        }
+        unsafe { self.resize(capacity) }
+    }
 
-        new_entry
+    /// Returns the live entry of `string`.
+    pub(crate) fn get(&self, hash: u64, string: &[u8]) -> Option<Entry> {
+        let entry = self.probe(hash, string).ok();
+        #[cfg(feature = "refcount")]
+        let entry = entry.filter(Entry::is_live);
+        entry
+    }
+
+    /// Creates an empty table with the same capacity and load factor.
+    ///
+    /// The capacity is not reserved if it can not be allocated.
+    pub(crate) fn empty_like(&self) -> Self {
+        let mut entries = Self::new(self.load_factor);
+        let _ = entries.reserve(self.len() + self.growth_left);
+        entries
+    }
+
+    /// Removes the entries which do not satisfy `f`, and returns them.
+    #[cfg(feature = "refcount")]
+    fn retain_entries<F>(&mut self, mut f: F) -> Vec<Entry>
+    where
+        F: FnMut(Entry) -> bool,
+    {
+        let items_count = self.len();
+        let (kept, removed): (Vec<_>, Vec<_>) = self
+            .occupied()
+            .take(items_count)
+            .partition(|&entry| f(entry));
+
+        self.dead = kept.iter().filter(|entry| !entry.is_live()).count();
+        if !removed.is_empty() {
+            unsafe {
+                ptr::write_bytes(self.data.as_ptr(), 0, self.capacity());
+                for entry in kept {
+                    Self::insert_unique(self.data, self.mask, entry);
+                }
+            }
+            self.growth_left += removed.len();
+        }
+        removed
+    }
+
+    /// Marks an entry whose reference count dropped to 0.
+    #[cfg(feature = "refcount")]
+    pub(crate) fn mark_dead(&mut self) {
+        self.dead += 1;
+    }
+
+    /// Unmarks an entry whose reference count rose from 0.
+    #[cfg(feature = "refcount")]
+    fn revive(&mut self) {
+        self.dead -= 1;
+    }
+
+    /// Removes the entries whose reference counts are 0 if the table is full,
+    /// and enough of them are found to defer its growth. Returns the removed entries.
+    #[cfg(feature = "refcount")]
+    pub(crate) fn remove_dead_if_full(&mut self) -> Vec<Entry> {
+        if self.growth_left > 0 || self.dead == 0 || self.dead * 8 < self.len() {
+            return Vec::new();
+        }
+        self.retain_entries(|entry| entry.is_live())
+    }
+
+    /// Reallocates the table to the smallest capacity which can hold all live entries,
+    /// and returns the number of freed bytes.
+    pub(crate) fn shrink_to_fit(&mut self) -> usize {
+        let before = self.allocated_memory();
+        let len = self.len();
+        if len == 0 {
+            *self = Self::new(self.load_factor);
+        } else {
+            let mut capacity = self.capacity();
+            while capacity > 4 && self.max_item_count(capacity / 2) >= len {
+                capacity /= 2;
+            }
+            if capacity < self.capacity() {
+                // keep the current table if the smaller one can not be allocated
+                let _ = unsafe { self.resize(capacity) };
+            }
+        }
+        before - self.allocated_memory()
+    }
+
+    /// All live entries.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = Entry> + '_ {
+        let entries = self.occupied();
+        #[cfg(feature = "refcount")]
+        let entries = entries.filter(Entry::is_live);
+        entries
+    }
+
+    /// All entries in the table, including those whose reference counts are 0.
+    fn occupied(&self) -> impl Iterator<Item = Entry> + '_ {
+        self.slots().iter().flatten().copied()
+    }
+
+    /// All slots of the table, including empty ones.
+    pub(crate) fn slots(&self) -> &[Option<Entry>] {
+        unsafe { slice::from_raw_parts(self.data.as_ptr(), self.capacity()) }
     }
 
     pub(crate) fn allocated_memory(&self) -> usize {
@@ -102,6 +415,53 @@ impl Entries {
     }
 }
 
+impl Entries {
+    /// Returns the matched entry,
+    /// or the position of the empty slot and its probe distance if not found.
+    #[inline]
+    fn probe(&self, hash: u64, string: &[u8]) -> Result<Entry, (usize, usize)> {
+        let mut pos = self.mask & hash as usize;
+        let mut dist = 0;
+        loop {
+            match unsafe { &*self.data.as_ptr().add(pos) } {
+                Some(entry) => {
+                    if entry.hash() == hash && entry.as_bytes() == string {
+                        return Ok(*entry);
+                    }
+                    dist += 1;
+                    pos = (pos + dist) & self.mask;
+                }
+                None => return Err((pos, dist)),
+            }
+        }
+    }
+
+    /// Returns the position of the first empty slot in the probe sequence of `hash`.
+    #[inline]
+    fn probe_empty(&self, hash: u64) -> usize {
+        let mut pos = self.mask & hash as usize;
+        let mut dist = 0;
+        while unsafe { (*self.data.as_ptr().add(pos)).is_some() } {
+            dist += 1;
+            pos = (pos + dist) & self.mask;
+        }
+        pos
+    }
+
+    /// An early growth is only allowed when the table is at least half of its load limit,
+    /// so clustered inputs can not make it grow without bound.
+    #[inline]
+    fn allow_early_grow(&self) -> bool {
+        self.len() * 2 >= self.max_item_count(self.capacity())
+    }
+
+    /// The number of live entries.
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.max_item_count(self.capacity()) - self.growth_left
+    }
+}
+
 impl Entries {
     /// where the Entries has allocated memory
     #[inline]
@@ -116,17 +476,21 @@ impl Entries {
 }
 
 impl Entries {
-    unsafe fn grow(&mut self) {
-        let cur_capacity = self.capacity();
+    unsafe fn grow(&mut self) -> Result<(), InternError> {
+        self.resize(Self::next_capacity(self.capacity()))
+    }
 
-        let new_capacity = Self::next_capacity(cur_capacity);
+    /// Moves all entries into a newly allocated table of `new_capacity`.
+    ///
+    /// The table is unchanged if the allocation fails.
+    unsafe fn resize(&mut self, new_capacity: usize) -> Result<(), InternError> {
         let new_mask = Self::capacity_to_mask(new_capacity);
 
         let new_data = {
             let layout = Self::layout_of_capacity(new_capacity);
             let allocated = alloc(layout);
             if allocated.is_null() {
-                panic!("oom")
+                return Err(InternError::AllocFailed);
             }
             NonNull::new_unchecked(allocated as *mut Option<Entry>)
         };
@@ -134,46 +498,34 @@ impl Entries {
         // zeroed
         ptr::write_bytes(new_data.as_ptr(), 0, new_capacity);
 
-        let cur_items_count = Self::max_item_count(cur_capacity);
-
-        {
-            let mut remaining_items_count = cur_items_count;
-
-            let cur_entry_slice = slice::from_raw_parts(self.data.as_ptr(), cur_capacity);
-
-            for e in cur_entry_slice {
-                match e {
-                    None => continue,
-                    Some(entry) => {
-                        let hash = entry.hash();
-                        let mut pos = (hash as usize) & new_mask;
-                        let mut dist = 0;
-                        let slot = loop {
-                            let slot = &mut *new_data.as_ptr().add(pos);
-                            if slot.is_none() {
-                                break slot;
-                            }
-
-                            dist += 1;
-                            pos = pos.wrapping_add(dist) & new_mask;
-                        };
-
-                        *slot = Some(*entry);
-                        remaining_items_count -= 1;
-                        if remaining_items_count == 0 {
-                            break;
-                        }
-                    }
-                }
-            }
+        let cur_items_count = self.len();
+        for entry in self.occupied().take(cur_items_count) {
+            Self::insert_unique(new_data, new_mask, entry);
         }
 
         // dealloc current data
         self.try_dealloc_data();
 
         self.data = new_data;
-        self.growth_left = Self::max_item_count(new_capacity) - cur_items_count;
+        self.growth_left = self.max_item_count(new_capacity) - cur_items_count;
         self.mask = new_mask;
+        Ok(())
+    }
+
+    /// Puts an entry, which is known to be absent, into the first empty slot of its probe sequence.
+    unsafe fn insert_unique(data: NonNull<Option<Entry>>, mask: usize, entry: Entry) {
+        let mut pos = (entry.hash() as usize) & mask;
+        let mut dist = 0;
+        let slot = loop {
+            let slot = &mut *data.as_ptr().add(pos);
+            if slot.is_none() {
+                break slot;
+            }
+
+            dist += 1;
+            pos = pos.wrapping_add(dist) & mask;
+        };
+        *slot = Some(entry);
     }
 
     unsafe fn try_dealloc_data(&self) {
@@ -203,8 +555,8 @@ impl Entries {
     }
 
     #[inline]
-    const fn max_item_count(capacity: usize) -> usize {
-        capacity / 4 * 3
+    const fn max_item_count(&self, capacity: usize) -> usize {
+        self.load_factor.max_item_count(capacity)
     }
 
     #[inline]
@@ -222,8 +574,11 @@ impl Drop for Entries {
 
 impl Default for Entries {
     fn default() -> Self {
-        Self::new()
+        Self::new(LoadFactor::default())
     }
 }
 
 unsafe impl Send for Entries {}
+
+// the table is only modified through `&mut Entries`
+unsafe impl Sync for Entries {}
@@ -0,0 +1,53 @@
+use crate::ScopedSto;
+use core::fmt;
+
+/// The error returned by [Repository::intern_unique](crate::Repository::intern_unique)
+/// when the string has already been interned.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DuplicateError<'a> {
+    existing: ScopedSto<'a>,
+}
+
+impl<'a> DuplicateError<'a> {
+    pub(crate) fn new(existing: ScopedSto<'a>) -> Self {
+        Self { existing }
+    }
+
+    /// The handle of the string interned before.
+    pub fn existing(&self) -> ScopedSto<'a> {
+        self.existing
+    }
+}
+
+impl fmt::Display for DuplicateError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "string {:?} is already interned", self.existing.as_str())
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for DuplicateError<'_> {}
+
+/// The error returned by [Repository::try_intern_in](crate::Repository::try_intern_in)
+/// when a string can not be interned.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum InternError {
+    /// The allocator failed to allocate memory for the string or the hash table.
+    AllocFailed,
+    /// The string is too large to be stored with its header in a single allocation.
+    StringTooLarge,
+}
+
+impl fmt::Display for InternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InternError::AllocFailed => f.write_str("memory allocation failed"),
+            InternError::StringTooLarge => f.write_str("string too large"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for InternError {}
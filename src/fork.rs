@@ -0,0 +1,92 @@
+use crate::{Repository, ScopedSto};
+use ahash::RandomState;
+use core::fmt;
+use core::hash::BuildHasher;
+
+/// A child of a [Repository] which shares the strings of its parent without copying them,
+/// and interns new strings into its own storage.
+///
+/// It is created by [Repository::fork], typically for short-lived speculative work
+/// whose new strings can be discarded by dropping the fork.
+///
+/// ## Lifetime
+/// A `ForkedRepository` borrows its parent, so the parent outlives it.
+/// Handles returned by the fork are bound to the fork, even if they point into the parent.
+/// Handles from the parent remain valid after the fork is dropped.
+///
+/// ## Compare
+/// A string is looked up in the parent before the fork's own storage.
+/// If the parent interns a string after the fork has interned it,
+/// later calls on the fork return the handle of the parent,
+/// which is not equal to the earlier handle of the fork.
+pub struct ForkedRepository<'p, const BUCKETS: usize = 64, H = RandomState> {
+    parent: &'p Repository<BUCKETS, H>,
+    local: Repository,
+}
+
+impl<'p, const BUCKETS: usize, H: BuildHasher> ForkedRepository<'p, BUCKETS, H> {
+    /// The parent [Repository].
+    pub fn parent(&self) -> &'p Repository<BUCKETS, H> {
+        self.parent
+    }
+
+    /// Interns a string.
+    ///
+    /// If the parent already holds `string`, the handle of the parent is returned.
+    /// Otherwise, `string` is interned into the fork's own storage.
+    pub fn intern<S>(&self, string: S) -> ScopedSto<'_>
+    where
+        S: AsRef<str>,
+    {
+        let string = string.as_ref();
+        match self.parent.find(string) {
+            Some(entry) => ScopedSto::new(entry),
+            None => ScopedSto::intern_in(string, &self.local),
+        }
+    }
+
+    /// Returns the interned string if the parent or the fork holds it, without inserting it.
+    pub fn get(&self, string: &str) -> Option<ScopedSto<'_>> {
+        self.parent
+            .find(string)
+            .or_else(|| self.local.find(string))
+            .map(ScopedSto::new)
+    }
+
+    /// Returns the number of bytes allocated by the fork itself, excluding its parent.
+    pub fn allocated_memory(&self) -> usize {
+        self.local.allocated_memory()
+    }
+}
+
+impl<const BUCKETS: usize, H> fmt::Debug for ForkedRepository<'_, BUCKETS, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ForkedRepository").finish()
+    }
+}
+
+impl<const BUCKETS: usize, H: BuildHasher> Repository<BUCKETS, H> {
+    /// Creates a [ForkedRepository], which sees all strings of this `Repository`
+    /// and interns new strings into its own storage.
+    ///
+    /// The fork has the same configuration as this `Repository`.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let hello = ScopedSto::intern_in("hello", &repository);
+    ///
+    /// let fork = repository.fork();
+    /// assert_eq!(fork.intern("hello"), hello);
+    /// let world = fork.intern("world");
+    /// assert_eq!(fork.get("world"), Some(world));
+    /// assert!(!repository.contains("world"));
+    /// ```
+    pub fn fork(&self) -> ForkedRepository<'_, BUCKETS, H> {
+        ForkedRepository {
+            parent: self,
+            local: Repository::with_config(self.config.clone()),
+        }
+    }
+}
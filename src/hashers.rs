@@ -0,0 +1,89 @@
+//! Hashers for maps keyed by interned strings.
+
+use core::hash::{BuildHasher, Hasher};
+
+/// A [Hasher] which returns a single written `u64` as is,
+/// such as the precomputed hash written by [PrehashedSto](crate::PrehashedSto).
+///
+/// Keys are not hashed again, so a map keyed by `PrehashedSto` hashes in O(1).
+/// Other writes are mixed by a simple multiplicative hash, which keeps the hasher correct
+/// but neither fast nor resistant to collision attacks.
+///
+/// It is only meant for maps keyed by `PrehashedSto`. [ScopedSto](crate::ScopedSto)
+/// hashes its string like `str`, so a map keyed by `ScopedSto` or `&str` gains nothing.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct StoHasher {
+    hash: u64,
+}
+
+/// An odd constant with well mixed bits, taken from `FxHash`.
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+impl Hasher for StoHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            let mut word = [0; 8];
+            word.copy_from_slice(chunk);
+            self.write_u64(u64::from_le_bytes(word));
+        }
+        let rest = chunks.remainder();
+        if !rest.is_empty() {
+            let mut word = [0; 8];
+            word[..rest.len()].copy_from_slice(rest);
+            self.write_u64(u64::from_le_bytes(word));
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.write_u64(i as u64)
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(i as u64)
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        // the first write is kept as is
+        self.hash = self.hash.rotate_left(5).wrapping_mul(SEED) ^ i;
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64)
+    }
+}
+
+/// The [BuildHasher] of [StoHasher], to key a `HashMap` by [PrehashedSto](crate::PrehashedSto).
+///
+/// ## Example
+/// ```
+/// # use std::collections::HashMap;
+/// # use sto::hashers::StoBuildHasher;
+/// # use sto::{PrehashedSto, Repository, ScopedSto};
+/// let repository = Repository::new();
+/// let mut map = HashMap::with_hasher(StoBuildHasher);
+/// map.insert(PrehashedSto(ScopedSto::intern_in("key", &repository)), 42);
+/// let key = PrehashedSto(ScopedSto::intern_in("key", &repository));
+/// assert_eq!(map.get(&key), Some(&42));
+/// ```
+#[derive(Debug, Default, Copy, Clone)]
+pub struct StoBuildHasher;
+
+impl BuildHasher for StoBuildHasher {
+    type Hasher = StoHasher;
+
+    #[inline]
+    fn build_hasher(&self) -> StoHasher {
+        StoHasher::default()
+    }
+}
@@ -4,35 +4,110 @@
 //! and allocates memory only when needed.
 //!
 //! - [Repository], a thread-safe struct where strings are stored,
+//! - [RepositoryBuilder], to configure a `Repository`,
 //! - [ScopedSto], a handle to access the interned string,
+//! - [LocalRepository] and [LocalSto], their single-threaded counterparts,
+//! - [ForkedRepository], a child of a `Repository` sharing its strings,
 //! - [Sto], an alias of `ScopedSto<'static>`,
+//! - [ScopedStoBytes], a handle to access interned bytes which are not necessarily UTF-8,
 //! - to intern a string, see [ScopedSto::intern_in],
 //! - to get the interned string, see [ScopedSto::as_str],
-//! - to check memory footprint, see [Repository::allocated_memory],
+//! - to key a `HashMap` by the precomputed hashes, see [PrehashedSto] and [hashers],
+//! - to check memory footprint, see [Repository::allocated_memory] and [Repository::memory_stats],
 //! - to access the global Repository provided by feature `global`, see [repository()],
-//! - to intern a string in the global Repository, see [Sto::from].
+//! - to intern a string in the global Repository, see [Sto::from],
+//! - to define more global Repositories, see [define_global_repository].
 //!
 //! ## Features
 //!
-//! | Name   | Default | Description                               |
-//! |--------|---------|-------------------------------------------|
-//! | global | ✅       | provide a shared global Repository        |
+//! | Name         | Default | Description                                   |
+//! |--------------|---------|-----------------------------------------------|
+//! | std          | ✅       | depend on `std` rather than only `alloc`      |
+//! | global       | ✅       | provide a shared global Repository            |
+//! | debug-timing |         | record where the time of interning is spent   |
+//! | rwlock       |         | lock buckets by read-write locks              |
+//! | serde        |         | implement `Serialize` and `Deserialize`       |
+//! | arbitrary    |         | implement `Arbitrary` for fuzzing             |
+//! | testing      |         | reset the global Repository between tests     |
+//! | thread_local |         | provide a Repository per thread               |
+//! | unicode      |         | intern strings in Unicode normalization form  |
+//! | refcount     |         | remove strings once all handles are released  |
+//! | spin         |         | lock buckets by spin locks without `std`      |
+//!
+//! Without the feature `std`, the crate is `no_std` and only requires a global allocator.
+//! The feature `spin` is then required, so buckets are locked by spin locks
+//! instead of `parking_lot` mutexes.
+//!
+//! With the feature `rwlock`, looking up interned strings, including interning a string
+//! which has been interned, only takes a shared lock of its bucket, so it does not block
+//! other lookups. A new string takes an exclusive lock after a missed lookup.
+//! This suits workloads interning a fixed set of strings once and looking them up from
+//! many threads, while it makes new strings slightly slower.
 #![deny(missing_debug_implementations, unreachable_pub, missing_docs)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(not(any(feature = "std", feature = "spin")))]
+compile_error!("the feature `spin` is required without the feature `std`");
+
+extern crate alloc;
 
-use std::cmp::Ordering;
-use std::fmt;
-use std::hash::{Hash, Hasher};
-use std::marker::PhantomData;
-use std::ops::Deref;
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::ptr::NonNull;
+#[cfg(feature = "global")]
+use std::sync::atomic::{AtomicPtr, Ordering as AtomicOrdering};
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impl;
 mod arena;
+mod builder;
+mod by_content;
+mod bytes;
 mod constants;
 mod entry;
+mod error;
+mod fork;
+pub mod hashers;
+mod local;
+#[cfg(feature = "global")]
+mod named;
+mod pair;
+mod prehashed;
 mod repository;
+mod scratch;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(feature = "std")]
+mod snapshot;
+mod stats;
+mod sync;
+mod timing;
 
+pub use crate::arena::ChunkId;
+pub use crate::builder::RepositoryBuilder;
+pub use crate::by_content::ByContent;
+pub use crate::bytes::{ScopedStoBytes, StoBytes};
 use crate::entry::Entry;
-pub use crate::repository::Repository;
+pub use crate::error::{DuplicateError, InternError};
+pub use crate::fork::ForkedRepository;
+#[cfg(feature = "thread_local")]
+pub use crate::local::local_repository;
+pub use crate::local::{LocalRepository, LocalSto};
+pub use crate::pair::ScopedPair;
+pub use crate::prehashed::PrehashedSto;
+pub use crate::repository::{Iter, Repository};
+#[cfg(feature = "serde")]
+pub use crate::serde_impl::RepositorySeed;
+pub use crate::stats::{BucketStat, MemoryStats};
+#[cfg(feature = "debug-timing")]
+pub use crate::timing::TimingReport;
 
 /// Represents an interned string.
 ///
@@ -63,7 +138,7 @@ pub use crate::repository::Repository;
 /// assert_eq!(a, "hello");
 /// {
 ///   let b = ScopedSto::intern_in("hello", &repository_a);
-///   // compared cheaply
+///   // compared cheaply by pointers, see `ScopedSto::ptr_eq`
 ///   assert_eq!(a, b)
 /// }
 ///
@@ -77,6 +152,19 @@ pub use crate::repository::Repository;
 /// }
 /// ```
 ///
+/// ## Hash
+/// `ScopedSto` hashes the contents of the string just like `str`,
+/// so a `HashMap` keyed by `ScopedSto` can be looked up with `&str`.
+/// [PrehashedSto] reuses the precomputed hash instead.
+/// ```
+/// # use std::collections::HashMap;
+/// # use sto::{ScopedSto, Repository};
+/// let repository = Repository::new();
+/// let mut map = HashMap::new();
+/// map.insert(ScopedSto::intern_in("key", &repository), 42);
+/// assert_eq!(map.get("key"), Some(&42));
+/// ```
+///
 /// ## Lifetime
 /// The lifetime of `ScopedSto` is tied to the [Repository] that actually stores data.
 /// That means when the `Repository` dropped, the `ScopedSto`s from it would be invalid.
@@ -97,8 +185,22 @@ pub struct ScopedSto<'a> {
 pub type Sto = ScopedSto<'static>;
 
 impl Hash for ScopedSto<'_> {
+    /// Hashes the contents of the string like `str` does, rather than the precomputed hash,
+    /// see [PrehashedSto] for the latter.
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.hash().hash(state)
+        self.as_str().hash(state)
+    }
+}
+
+/// A `ScopedSto` hashes and orders like its string, so a map keyed by handles
+/// can be looked up with `&str`.
+///
+/// Handles are compared by pointers, which only agrees with comparing strings
+/// for handles of the same [Repository]. Use [ByContent] to key a map by handles
+/// of different `Repository`s.
+impl Borrow<str> for ScopedSto<'_> {
+    fn borrow(&self) -> &str {
+        self.as_str()
     }
 }
 
@@ -115,27 +217,357 @@ impl<'a> ScopedSto<'a> {
         self.entry.as_str()
     }
 
+    /// The bytes of the interned string.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let s = ScopedSto::intern_in("hello", &repository);
+    /// assert_eq!(s.as_bytes(), b"hello");
+    /// ```
+    #[inline]
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.entry.as_bytes()
+    }
+
+    /// The interned string, which lives as long as the [Repository] rather than the handle.
+    ///
+    /// This is the same as [ScopedSto::as_str]. The string data is stored in the `Repository`,
+    /// so the returned `&str` can be kept after the handle itself goes out of scope.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let mut stored: Vec<&str> = Vec::new();
+    /// let mut store = |s: &str| {
+    ///     let handle = ScopedSto::intern_in(s, &repository);
+    ///     // the handle is dropped here, but the string is still alive
+    ///     stored.push(handle.as_repo_str());
+    /// };
+    /// store("hello");
+    /// store("world");
+    /// assert_eq!(stored, ["hello", "world"]);
+    /// ```
+    pub fn as_repo_str(&self) -> &'a str {
+        self.entry.as_str()
+    }
+
     /// The precomputed hash.
     pub fn hash(&self) -> u64 {
         self.entry.hash()
     }
 
+    /// The precomputed hash folded into 32 bits, to save space in side tables.
+    ///
+    /// It is derived from [ScopedSto::hash] by xoring its high and low halves,
+    /// `(hash ^ (hash >> 32)) as u32`, so it can be recomputed from a 64-bit hash,
+    /// e.g. one returned by [Repository::hash_of].
+    ///
+    /// Different strings collide much more often in 32 bits, after about 2^16 strings
+    /// rather than 2^32, so the strings must still be compared on equal hashes.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let s = ScopedSto::intern_in("hello", &repository);
+    /// let hash = repository.hash_of("hello");
+    /// assert_eq!(s.hash32(), (hash ^ (hash >> 32)) as u32);
+    /// ```
+    #[inline]
+    pub fn hash32(&self) -> u32 {
+        let hash = self.hash();
+        (hash ^ (hash >> 32)) as u32
+    }
+
     /// The length of the interned string.
     #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
         self.as_str().len()
     }
+
+    /// The precomputed hash, the length, and the pointer to the bytes of the interned string.
+    ///
+    /// This reads the stored header once, which is cheaper than calling
+    /// [ScopedSto::hash], [ScopedSto::len] and [str::as_ptr] separately.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let s = ScopedSto::intern_in("hello", &repository);
+    /// let (hash, len, ptr) = s.parts();
+    /// assert_eq!(hash, s.hash());
+    /// assert_eq!(len, s.len());
+    /// assert_eq!(ptr, s.as_str().as_ptr());
+    /// ```
+    pub fn parts(&self) -> (u64, usize, *const u8) {
+        self.entry.parts()
+    }
+
+    /// The opaque pointer identifying the interned string, to be passed as a single pointer
+    /// e.g. through FFI, and turned back into a handle by [ScopedSto::from_raw].
+    ///
+    /// It points into the header of the string, not to its bytes, see [ScopedSto::parts]
+    /// for the latter. It must not be dereferenced.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let s = ScopedSto::intern_in("hello", &repository);
+    /// let raw = s.as_raw();
+    /// assert_eq!(unsafe { ScopedSto::from_raw(raw) }, s);
+    /// ```
+    #[inline]
+    pub fn as_raw(&self) -> *const u8 {
+        self.entry.0.as_ptr()
+    }
+
+    /// Turns a pointer returned by [ScopedSto::as_raw] back into a handle.
+    ///
+    /// ## Safety
+    /// - `ptr` must be returned by [ScopedSto::as_raw] of a handle, unchanged.
+    ///   Any other pointer, even into the memory of a [Repository], is undefined behavior.
+    /// - The `Repository` of that handle must be alive for `'a`, and not have removed
+    ///   the string, e.g. by [Repository::clear] or [Repository::retain].
+    /// - The handle must come from this build of the crate with the same features,
+    ///   since the layout of interned strings depends on them,
+    ///   so the pointer can not be passed to another process or library.
+    #[inline]
+    pub unsafe fn from_raw(ptr: *const u8) -> Self {
+        debug_assert!(!ptr.is_null(), "null pointer passed to ScopedSto::from_raw");
+        Self::new(Entry(NonNull::new_unchecked(ptr as *mut u8)))
+    }
+
+    /// The dense id of the interned string, if its [Repository] is built with
+    /// [RepositoryBuilder::indexed], otherwise `None`.
+    ///
+    /// It is stored along with the string, so it is read without locking the `Repository`,
+    /// and [Repository::resolve] turns it back into the handle.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::builder().indexed().build();
+    /// let hello = ScopedSto::intern_in("hello", &repository);
+    /// let world = ScopedSto::intern_in("world", &repository);
+    /// assert_eq!((hello.id(), world.id()), (Some(0), Some(1)));
+    /// assert_eq!(repository.resolve(1), Some(world));
+    ///
+    /// let repository = Repository::new();
+    /// assert_eq!(ScopedSto::intern_in("hello", &repository).id(), None);
+    /// ```
+    #[inline]
+    pub fn id(&self) -> Option<u32> {
+        self.entry.id()
+    }
+
+    /// The bytes of the interned string followed by the sentinel of its [Repository].
+    ///
+    /// ## Safety
+    /// The repository must be configured with a sentinel, see [RepositoryBuilder::sentinel].
+    /// Otherwise the last byte is out of the bounds of the interned string.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::with_sentinel(b'\0');
+    /// let s = ScopedSto::intern_in("hello", &repository);
+    /// assert_eq!(s.as_str(), "hello");
+    /// assert_eq!(unsafe { s.as_str_with_sentinel() }, b"hello\0");
+    /// ```
+    pub unsafe fn as_str_with_sentinel(&self) -> &'a [u8] {
+        self.entry.as_bytes_with_sentinel()
+    }
+
+    /// Checks whether both handles point to the same interned string.
+    ///
+    /// This is what `==` does between two `ScopedSto`s: comparing a pointer rather than
+    /// the strings. Handles of the same content from different [Repository]s are not equal.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let a = ScopedSto::intern_in("hello", &repository);
+    /// let b = ScopedSto::intern_in("hello", &repository);
+    /// assert!(a.ptr_eq(&b));
+    ///
+    /// let other = Repository::new();
+    /// assert!(!a.ptr_eq(&ScopedSto::intern_in("hello", &other)));
+    /// ```
+    #[inline]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.entry == other.entry
+    }
+
+    /// Orders handles by the addresses of their interned strings, comparing the strings
+    /// only when different handles share their hash and length.
+    ///
+    /// The order is consistent with [ScopedSto::ptr_eq] but arbitrary, not lexicographical,
+    /// which is enough to sort or deduplicate handles cheaply.
+    ///
+    /// It is only meaningful for handles of the same [Repository]. Returns `None` if
+    /// the handles are detected to come from different repositories, that is,
+    /// they point to different copies of the same string. Handles of different strings
+    /// from different repositories can not be detected, and are still ordered by addresses.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// # use std::cmp::Ordering;
+    /// let repository = Repository::new();
+    /// let mut stos = ["b", "a", "c", "a"].map(|s| ScopedSto::intern_in(s, &repository)).to_vec();
+    /// stos.sort_unstable_by(|a, b| a.ptr_cmp(b).unwrap());
+    /// stos.dedup_by(|a, b| a.ptr_eq(b));
+    /// assert_eq!(stos.len(), 3);
+    ///
+    /// let a = ScopedSto::intern_in("a", &repository);
+    /// let other = Repository::new();
+    /// assert_eq!(a.ptr_cmp(&a), Some(Ordering::Equal));
+    /// assert_eq!(a.ptr_cmp(&ScopedSto::intern_in("a", &other)), None);
+    /// ```
+    #[inline]
+    pub fn ptr_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.ptr_eq(other) {
+            return Some(Ordering::Equal);
+        }
+        let (hash, len, ptr) = self.parts();
+        let (other_hash, other_len, other_ptr) = other.parts();
+        if hash == other_hash && len == other_len && self.as_bytes() == other.as_bytes() {
+            None
+        } else {
+            Some(ptr.cmp(&other_ptr))
+        }
+    }
+
+    /// Copies the bytes of the interned string into a fixed-size array.
+    ///
+    /// The rest of the array is padded with zeros.
+    /// Returns `None` if the string is longer than `N`.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let key = ScopedSto::intern_in("key", &repository);
+    /// assert_eq!(key.to_array::<4>(), Some(*b"key\0"));
+    /// assert_eq!(key.to_array::<2>(), None);
+    /// ```
+    pub fn to_array<const N: usize>(&self) -> Option<[u8; N]> {
+        let bytes = self.as_str().as_bytes();
+        if bytes.len() > N {
+            return None;
+        }
+        let mut array = [0; N];
+        array[..bytes.len()].copy_from_slice(bytes);
+        Some(array)
+    }
+
+    /// Checks whether the interned string starts with `prefix`, like [str::starts_with].
+    ///
+    /// It is the same as going through `Deref`, but also works where auto-deref does not apply.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let s = ScopedSto::intern_in("hello world", &repository);
+    /// assert!(s.starts_with("hello"));
+    /// assert!(s.ends_with("world"));
+    /// assert!(s.contains("o w"));
+    /// ```
+    #[inline]
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        self.as_str().starts_with(prefix)
+    }
+
+    /// Checks whether the interned string ends with `suffix`, like [str::ends_with].
+    #[inline]
+    pub fn ends_with(&self, suffix: &str) -> bool {
+        self.as_str().ends_with(suffix)
+    }
+
+    /// Checks whether the interned string contains `pattern`, like [str::contains].
+    #[inline]
+    pub fn contains(&self, pattern: &str) -> bool {
+        self.as_str().contains(pattern)
+    }
 }
 
 impl<'a> ScopedSto<'a> {
     /// Intern a string in the given [Repository].
     #[inline(always)]
-    pub fn intern_in<S>(string: S, repository: &'a Repository) -> Self
+    pub fn intern_in<S, const BUCKETS: usize, H>(
+        string: S,
+        repository: &'a Repository<BUCKETS, H>,
+    ) -> Self
     where
         S: AsRef<str>,
+        H: BuildHasher,
     {
         Self::new(repository.get_or_insert(string.as_ref()))
     }
+
+    /// Intern a string in the given [Repository], and returns whether it is newly inserted.
+    ///
+    /// See [Repository::get_or_insert_full].
+    #[inline]
+    pub fn intern_in_full<S, const BUCKETS: usize, H>(
+        string: S,
+        repository: &'a Repository<BUCKETS, H>,
+    ) -> (Self, bool)
+    where
+        S: AsRef<str>,
+        H: BuildHasher,
+    {
+        repository.get_or_insert_full(string.as_ref())
+    }
+
+    /// Releases a handle returned by an interning method, like [ScopedSto::intern_in],
+    /// and returns whether the string is to be removed.
+    ///
+    /// Every call of an interning method increments the reference count of the string,
+    /// while copies of handles and handles returned by lookups, like [Repository::get],
+    /// are not counted. Once the count drops to 0, the string is no longer found by lookups,
+    /// and it is removed when its bucket needs room for a new string, whose memory may reuse
+    /// the memory of the removed one. Until then, it is still counted by [Repository::len],
+    /// and interning it again revives it.
+    ///
+    /// Strings are never removed from a [Repository] which assigns ids,
+    /// see [RepositoryBuilder::indexed].
+    ///
+    /// ## Safety
+    /// The handle must come from `repository`. Once the count drops to 0, no handle of
+    /// the string may be used anymore, including copies and handles returned by lookups.
+    ///
+    /// ## Panics
+    /// Panics if the string is released more times than it is interned.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let a = ScopedSto::intern_in("hello", &repository);
+    /// let b = ScopedSto::intern_in("hello", &repository);
+    /// assert!(!unsafe { a.release(&repository) });
+    /// assert!(unsafe { b.release(&repository) });
+    /// assert!(repository.get("hello").is_none());
+    /// ```
+    #[cfg(feature = "refcount")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "refcount")))]
+    pub unsafe fn release<const BUCKETS: usize, H>(
+        self,
+        repository: &Repository<BUCKETS, H>,
+    ) -> bool
+    where
+        H: BuildHasher,
+    {
+        repository.release(self.entry)
+    }
 }
 
 unsafe impl Send for ScopedSto<'_> {}
@@ -148,27 +580,95 @@ impl PartialEq<&str> for ScopedSto<'_> {
     }
 }
 
+/// Compares with an unsized `str`, such as the target of a `Box<str>`.
+///
+/// ## Example
+/// ```
+/// # use sto::{Repository, ScopedSto};
+/// let repository = Repository::new();
+/// let boxed: Box<str> = "hello".into();
+/// let sto = ScopedSto::intern_in("hello", &repository);
+/// assert!(matches!(Some(sto), Some(s) if s == *boxed));
+/// assert!(matches!(Some(&*boxed), Some(s) if *s == sto));
+/// ```
+impl PartialEq<str> for ScopedSto<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<ScopedSto<'_>> for str {
+    fn eq(&self, other: &ScopedSto<'_>) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<ScopedSto<'_>> for &str {
+    fn eq(&self, other: &ScopedSto<'_>) -> bool {
+        *self == other.as_str()
+    }
+}
+
 impl PartialEq<String> for ScopedSto<'_> {
     fn eq(&self, other: &String) -> bool {
         self.as_str() == other.as_str()
     }
 }
 
+impl PartialEq<ScopedSto<'_>> for String {
+    fn eq(&self, other: &ScopedSto<'_>) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
 impl<'a> AsRef<str> for ScopedSto<'a> {
     fn as_ref(&self) -> &'a str {
         self.as_str()
     }
 }
 
+impl<'a> AsRef<[u8]> for ScopedSto<'a> {
+    fn as_ref(&self) -> &'a [u8] {
+        self.as_bytes()
+    }
+}
+
 impl<'a> From<ScopedSto<'a>> for &'a str {
     fn from(value: ScopedSto<'a>) -> Self {
         value.as_str()
     }
 }
 
+/// Copies the interned string into a new `String`.
+///
+/// Unlike most of this crate, this allocates. Convert into `&str` to borrow it instead.
+///
+/// ## Example
+/// ```
+/// # use sto::{Repository, ScopedSto};
+/// let repository = Repository::new();
+/// let owned = String::from(ScopedSto::intern_in("hello", &repository));
+/// drop(repository);
+/// assert_eq!(owned, "hello");
+/// ```
+impl From<ScopedSto<'_>> for String {
+    fn from(value: ScopedSto<'_>) -> Self {
+        value.as_str().into()
+    }
+}
+
+/// Copies the interned string into a new `Box<str>`.
+///
+/// Unlike most of this crate, this allocates. Convert into `&str` to borrow it instead.
+impl From<ScopedSto<'_>> for Box<str> {
+    fn from(value: ScopedSto<'_>) -> Self {
+        value.as_str().into()
+    }
+}
+
 impl PartialOrd<Self> for ScopedSto<'_> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.as_str().partial_cmp(other.as_str())
+        Some(self.cmp(other))
     }
 }
 
@@ -178,6 +678,30 @@ impl Ord for ScopedSto<'_> {
     }
 }
 
+impl PartialOrd<&str> for ScopedSto<'_> {
+    fn partial_cmp(&self, other: &&str) -> Option<Ordering> {
+        Some(self.as_str().cmp(*other))
+    }
+}
+
+impl PartialOrd<String> for ScopedSto<'_> {
+    fn partial_cmp(&self, other: &String) -> Option<Ordering> {
+        Some(self.as_str().cmp(other.as_str()))
+    }
+}
+
+impl PartialOrd<ScopedSto<'_>> for &str {
+    fn partial_cmp(&self, other: &ScopedSto<'_>) -> Option<Ordering> {
+        Some((*self).cmp(other.as_str()))
+    }
+}
+
+impl PartialOrd<ScopedSto<'_>> for String {
+    fn partial_cmp(&self, other: &ScopedSto<'_>) -> Option<Ordering> {
+        Some(self.as_str().cmp(other.as_str()))
+    }
+}
+
 impl<'a> Deref for ScopedSto<'a> {
     type Target = str;
 
@@ -204,18 +728,87 @@ impl fmt::Debug for ScopedSto<'_> {
 #[cfg(feature = "global")]
 #[cfg_attr(docsrs, doc(cfg(feature = "global")))]
 pub fn repository() -> &'static Repository {
-    use once_cell::sync::OnceCell;
-    static REPO: OnceCell<Repository> = OnceCell::new();
+    GLOBAL_REPOSITORY.get_or_init(Repository::new)
+}
+
+/// Installs `repository` as the global [Repository], e.g. to configure its seeds or
+/// load factor, and returns it back if the global `Repository` is already initialized.
+///
+/// The global `Repository` is initialized by the first call of [repository()],
+/// including those made by [Sto::from] on any thread, so call this early in `main`,
+/// before any other thread is spawned.
+///
+/// The global `Repository` always has the default number of buckets and hasher type.
+///
+/// ## Example
+/// ```
+/// # use sto::{init_global_repository, repository, Repository, Sto};
+/// assert!(init_global_repository(Repository::with_seed([1, 2, 3, 4])).is_ok());
+/// let hash = Repository::with_seed([1, 2, 3, 4]).hash_of("hello");
+/// assert_eq!(repository().hash_of("hello"), hash);
+///
+/// Sto::from("hello");
+/// assert!(init_global_repository(Repository::new()).is_err());
+/// ```
+#[cfg(feature = "global")]
+#[cfg_attr(docsrs, doc(cfg(feature = "global")))]
+// the repository is given back as is, like `OnceCell::set`
+#[allow(clippy::result_large_err)]
+pub fn init_global_repository(repository: Repository) -> Result<(), Repository> {
+    GLOBAL_REPOSITORY.set(repository)
+}
+
+#[cfg(feature = "global")]
+#[doc(hidden)]
+pub mod __private {
+    pub use once_cell::sync::OnceCell;
+}
 
-    REPO.get_or_init(Repository::new)
+#[cfg(feature = "global")]
+static GLOBAL_REPOSITORY: once_cell::sync::OnceCell<Repository> = once_cell::sync::OnceCell::new();
+
+/// Removes all strings interned in the global [Repository], so tests using [Sto::from]
+/// do not see the strings of each other.
+///
+/// ## Safety
+/// Every [Sto] interned before the reset dangles, so none of them may be used afterward,
+/// including those held by other threads. Tests sharing the global `Repository` must not
+/// run concurrently with the reset, e.g. run them with `--test-threads=1`.
+///
+/// ## Example
+/// ```
+/// # use sto::{repository, reset_global_repository, Sto};
+/// Sto::from("hello");
+/// unsafe { reset_global_repository() };
+/// assert!(repository().is_empty());
+/// assert_eq!(Sto::from("hello"), "hello");
+/// ```
+#[cfg(feature = "testing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+pub unsafe fn reset_global_repository() {
+    for slot in &TINY {
+        slot.store(core::ptr::null_mut(), AtomicOrdering::Release);
+    }
+    repository().clear_shared();
 }
 
+// used only to initialize the array below
+#[cfg(feature = "global")]
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_SLOT: AtomicPtr<u8> = AtomicPtr::new(core::ptr::null_mut());
+/// The global handles of 128 ASCII characters and the empty string
+#[cfg(feature = "global")]
+static TINY: [AtomicPtr<u8>; 129] = [EMPTY_SLOT; 129];
+
 #[cfg(feature = "global")]
 impl ScopedSto<'static> {
     /// A shortcut to intern a string in the default global shared [Repository].
     ///
     /// Returns a 'static [ScopedSto].
     ///
+    /// The empty string and single-byte ASCII strings are served from a lock-free table
+    /// after their first interning.
+    ///
     /// # Example
     /// ```
     /// # #[cfg(feature = "global")]
@@ -235,7 +828,27 @@ impl ScopedSto<'static> {
     where
         S: AsRef<str>,
     {
-        Self::intern_in(string, repository())
+        let string = string.as_ref();
+        // a cached handle is not counted, so it could outlive its string
+        if string.len() <= 1 && cfg!(not(feature = "refcount")) {
+            Self::intern_tiny(string)
+        } else {
+            Self::intern_in(string, repository())
+        }
+    }
+
+    /// Interns the empty string or a single-byte string, which is always ASCII.
+    #[inline]
+    fn intern_tiny(string: &str) -> Self {
+        let slot = &TINY[string.bytes().next().map_or(128, usize::from)];
+        match NonNull::new(slot.load(AtomicOrdering::Acquire)) {
+            Some(ptr) => Self::new(Entry(ptr)),
+            None => {
+                let sto = Self::intern_in(string, repository());
+                slot.store(sto.entry.0.as_ptr(), AtomicOrdering::Release);
+                sto
+            }
+        }
     }
 }
 
@@ -275,8 +888,11 @@ impl std::str::FromStr for ScopedSto<'static> {
 
 #[cfg(test)]
 mod tests {
-    use crate::constants::{CHUNK_DEFAULT_CAPACITY, ENTRIES_INITIAL_CAPACITY};
-    use crate::{Repository, ScopedSto};
+    use crate::arena::HEADER_SIZE;
+    use crate::constants::{
+        BUCKET_NUMBER, CHUNK_DEFAULT_CAPACITY, ENTRIES_INITIAL_CAPACITY, ENTRIES_MIN_CAPACITY,
+    };
+    use crate::{BucketStat, LocalRepository, Repository, ScopedPair, ScopedSto};
     use std::mem::size_of;
 
     #[test]
@@ -319,20 +935,1846 @@ mod tests {
     }
 
     #[test]
-    #[cfg(feature = "global")]
-    fn test_sto() {
-        use crate::Sto;
+    fn test_max_probe_length() {
+        let strings = (0..10_000).map(|i| i.to_string()).collect::<Vec<_>>();
 
-        let a = Sto::from("hello world");
-        let b = Sto::from("hello world");
-        assert_eq!(a, b);
-        assert_eq!(a.as_str(), b.as_str());
-        assert_eq!(a.hash(), b.hash());
+        let bounded = Repository::builder().max_probe_length(1).build();
+        let handles = strings
+            .iter()
+            .map(|s| ScopedSto::intern_in(s, &bounded))
+            .collect::<Vec<_>>();
+        for (s, handle) in strings.iter().zip(handles) {
+            assert_eq!(handle, *s);
+            assert_eq!(ScopedSto::intern_in(s, &bounded), handle);
+        }
 
-        let a = Sto::from("hello");
-        let b = Sto::from("world");
-        assert_ne!(a, b);
-        assert_ne!(a.as_str(), b.as_str());
-        assert_ne!(a.hash(), b.hash());
+        let unbounded = Repository::new();
+        strings.iter().for_each(|s| {
+            ScopedSto::intern_in(s, &unbounded);
+        });
+        assert!(bounded.allocated_memory() >= unbounded.allocated_memory());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_get_many() {
+        let repo = Repository::new();
+        assert!(repo.get_many(&["hello", "world"]).is_empty());
+        assert_eq!(repo.allocated_memory(), 0);
+
+        let strings = (0..1000).map(|i| i.to_string()).collect::<Vec<_>>();
+        let handles = strings
+            .iter()
+            .step_by(2)
+            .map(|s| ScopedSto::intern_in(s, &repo))
+            .collect::<Vec<_>>();
+
+        let queries = strings.iter().map(String::as_str).collect::<Vec<_>>();
+        let found = repo.get_many(&queries);
+        assert_eq!(found.len(), handles.len());
+        for handle in handles {
+            assert_eq!(found[handle.as_str()], handle);
+        }
+    }
+
+    #[test]
+    fn test_to_sorted_vec() {
+        let repo = Repository::new();
+        assert!(repo.to_sorted_vec().is_empty());
+
+        let mut strings = (0..1000).map(|i| i.to_string()).collect::<Vec<_>>();
+        strings.iter().chain(strings.iter()).for_each(|s| {
+            ScopedSto::intern_in(s, &repo);
+        });
+        strings.sort_unstable();
+        assert_eq!(repo.to_sorted_vec(), strings);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_collisions() {
+        let repo = Repository::new();
+        (0..10_000).for_each(|i| {
+            ScopedSto::intern_in(i.to_string(), &repo);
+        });
+        assert!(repo.collisions().is_empty());
+    }
+
+    #[test]
+    fn test_intern_in_with_chunk() {
+        let repo = Repository::new();
+        let strings = (0..10_000).map(|i| i.to_string()).collect::<Vec<_>>();
+        let chunks = strings
+            .iter()
+            .map(|s| repo.intern_in_with_chunk(s).1)
+            .collect::<Vec<_>>();
+        for (s, chunk) in strings.iter().zip(chunks) {
+            assert_eq!(repo.intern_in_with_chunk(s).1, chunk);
+        }
+
+        let large_string = "test".repeat(CHUNK_DEFAULT_CAPACITY);
+        let (a, large_chunk) = repo.intern_in_with_chunk(&large_string);
+        assert_eq!(a, large_string);
+        assert_eq!(repo.intern_in_with_chunk(&large_string).1, large_chunk);
+    }
+
+    #[test]
+    fn test_intern_chars() {
+        let repo = Repository::new();
+        let a = repo.intern_chars("hello".chars());
+        assert_eq!(a, ScopedSto::intern_in("hello", &repo));
+
+        // re-entered while collecting
+        let b = repo.intern_chars("ab".chars().inspect(|_| {
+            repo.intern_chars("nested".chars());
+        }));
+        assert_eq!(b, "ab");
+        assert_eq!(repo.intern_chars("".chars()), "");
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut repo = Repository::new();
+        (0..100_000).for_each(|i| {
+            ScopedSto::intern_in(i.to_string(), &repo);
+        });
+
+        let before = repo.memory_stats();
+        assert_eq!(repo.retain(|s| s.len() < 5 && s.ends_with('0')), 99_000);
+        let after = repo.memory_stats();
+        assert!(after.arena_bytes < before.arena_bytes);
+        assert_eq!(after.entries_bytes, before.entries_bytes);
+        let retained = repo.to_sorted_vec();
+        assert_eq!(retained.len(), 1000);
+        assert!(retained.iter().all(|s| s.ends_with("0")));
+
+        // the rebuilt tables still work
+        for s in (0..10_000).map(|i| i.to_string()) {
+            assert_eq!(ScopedSto::intern_in(&s, &repo), s);
+        }
+        assert_eq!(repo.to_sorted_vec().len(), 10_000);
+    }
+
+    #[test]
+    fn test_shrink_to_fit() {
+        let mut repo = Repository::new();
+        (0..10_000).for_each(|i| {
+            ScopedSto::intern_in(i.to_string(), &repo);
+        });
+        repo.retain(|s| s.len() < 3);
+        let before = repo.allocated_memory();
+        let freed = repo.shrink_to_fit();
+        assert!(freed > 0);
+        assert_eq!(repo.allocated_memory(), before - freed);
+        assert_eq!(repo.shrink_to_fit(), 0);
+
+        // the shrunk tables still work
+        for s in (0..100).map(|i| i.to_string()) {
+            assert_eq!(repo.get(&s).unwrap(), s);
+        }
+        assert_eq!(repo.len(), 100);
+        assert!(repo.get("100").is_none());
+    }
+
+    #[test]
+    fn test_memory_stats() {
+        let repo = Repository::new();
+        assert_eq!(repo.memory_stats(), Default::default());
+
+        (0..10_000).for_each(|i| {
+            ScopedSto::intern_in(i.to_string(), &repo);
+        });
+        let stats = repo.memory_stats();
+        assert_eq!(stats.entry_count, 10_000);
+        assert_eq!(stats.largest_chunk_bytes, CHUNK_DEFAULT_CAPACITY);
+        assert_eq!(
+            stats.arena_bytes,
+            stats.chunk_count * CHUNK_DEFAULT_CAPACITY
+        );
+        assert_eq!(stats.total_bytes(), repo.allocated_memory());
+        let used = (0..10_000)
+            .map(|i| HEADER_SIZE + i.to_string().len())
+            .sum::<usize>();
+        assert_eq!(stats.wasted_bytes, stats.arena_bytes - used);
+
+        ScopedSto::intern_in("x".repeat(CHUNK_DEFAULT_CAPACITY), &repo);
+        let stats = repo.memory_stats();
+        assert_eq!(stats.entry_count, 10_001);
+        assert!(stats.largest_chunk_bytes > CHUNK_DEFAULT_CAPACITY);
+        assert_eq!(stats.total_bytes(), repo.allocated_memory());
+    }
+
+    #[test]
+    fn test_chunk_capacity() {
+        let repo = Repository::with_chunk_capacity(1 << 16);
+        let stos = (0..100)
+            .map(|i| ScopedSto::intern_in(i.to_string().repeat(CHUNK_DEFAULT_CAPACITY / 2), &repo))
+            .collect::<Vec<_>>();
+        for (i, sto) in stos.iter().enumerate() {
+            assert_eq!(
+                sto.as_str(),
+                i.to_string().repeat(CHUNK_DEFAULT_CAPACITY / 2)
+            );
+        }
+        // no dedicated chunk for strings larger than the default capacity
+        let stats = repo.memory_stats();
+        assert_eq!(stats.largest_chunk_bytes, 1 << 16);
+        assert_eq!(stats.arena_bytes, stats.chunk_count << 16);
+
+        // the smallest chunk only fits an empty string
+        let repo = Repository::with_chunk_capacity(crate::arena::MIN_CHUNK_CAPACITY);
+        assert_eq!(ScopedSto::intern_in("hello", &repo), "hello");
+        assert_eq!(ScopedSto::intern_in("", &repo), "");
+        assert_eq!(ScopedSto::intern_in("world", &repo), "world");
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk capacity must be at least")]
+    fn test_chunk_capacity_too_small() {
+        Repository::with_chunk_capacity(1);
+    }
+
+    #[test]
+    fn test_ptr_cmp() {
+        let repo = Repository::new();
+        let mut stos = (0..10_000)
+            .map(|i| ScopedSto::intern_in((i % 1000).to_string(), &repo))
+            .collect::<Vec<_>>();
+        stos.sort_unstable_by(|a, b| a.ptr_cmp(b).unwrap());
+        stos.dedup_by(|a, b| a.ptr_eq(b));
+        assert_eq!(stos.len(), 1000);
+
+        let other = Repository::new();
+        let a = ScopedSto::intern_in("a", &repo);
+        let other_a = ScopedSto::intern_in("a", &other);
+        let other_b = ScopedSto::intern_in("b", &other);
+        assert!(!a.ptr_eq(&other_a));
+        assert_eq!(a.ptr_cmp(&other_a), None);
+        assert!(a.ptr_cmp(&other_b).is_some());
+    }
+
+    #[test]
+    fn test_intern_with_hash() {
+        let repo = Repository::new();
+        let stos = (0..10_000)
+            .map(|i| ScopedSto::intern_in(i.to_string(), &repo))
+            .collect::<Vec<_>>();
+
+        let copy = Repository::new();
+        for sto in &stos {
+            assert_eq!(copy.hash_of(sto.as_str()), sto.hash());
+            let copied = copy.intern_with_hash(sto.hash(), sto.as_str());
+            assert_eq!(copied, sto.as_str());
+            assert_eq!(copied.hash(), sto.hash());
+            assert_eq!(copy.get(sto.as_str()), Some(copied));
+        }
+        assert_eq!(copy.len(), 10_000);
+    }
+
+    #[test]
+    fn test_try_intern_in() {
+        use crate::arena::Arena;
+        use crate::builder::Config;
+        use crate::InternError;
+
+        let repo = Repository::new();
+        for i in 0..10_000 {
+            let s = i.to_string();
+            assert_eq!(
+                repo.try_intern_in(&s).unwrap(),
+                ScopedSto::intern_in(&s, &repo)
+            );
+        }
+        assert_eq!(repo.len(), 10_000);
+
+        let mut arena = Arena::new();
+        assert_eq!(
+            arena.try_alloc(0, usize::MAX - 8, &Config::default()),
+            Err(InternError::StringTooLarge)
+        );
+        assert_eq!(arena.allocated_memory(), 0);
+    }
+
+    #[test]
+    fn test_intern_near_max_len() {
+        use crate::arena::Arena;
+        use crate::builder::Config;
+        use crate::InternError;
+
+        let config = Config::default();
+        let mut arena = Arena::new();
+        // the needed bytes fit in `usize`, but exceed the limit of an allocation
+        for str_len in [
+            isize::MAX as usize - 16,
+            isize::MAX as usize,
+            usize::MAX - 64,
+        ] {
+            assert_eq!(
+                arena.try_alloc(0, str_len, &config),
+                Err(InternError::StringTooLarge)
+            );
+        }
+        assert_eq!(arena.allocated_memory(), 0);
+
+        let repo = Repository::new();
+        let large = "0123456789abcdef".repeat(CHUNK_DEFAULT_CAPACITY * 1000 / 16);
+        let sto = repo.try_intern_in(&large).unwrap();
+        assert_eq!(sto, large);
+        assert_eq!(ScopedSto::intern_in(&large, &repo), sto);
+        assert_eq!(ScopedSto::intern_in("small", &repo), "small");
+    }
+
+    #[test]
+    fn test_intern_empty() {
+        let repos = [
+            Repository::new(),
+            Repository::with_string_alignment(64),
+            Repository::with_sentinel(b'\0'),
+            Repository::builder().indexed().build(),
+        ];
+        for repo in &repos {
+            let a = ScopedSto::intern_in("", repo);
+            let b = ScopedSto::intern_in("", repo);
+            assert!(a.ptr_eq(&b));
+            assert_eq!(a.len(), 0);
+            assert_eq!(a.as_str(), "");
+            assert_eq!(
+                a.as_str().as_ptr() as usize % repo.config.string_alignment,
+                0
+            );
+            assert_eq!(repo.len(), 1);
+            assert_eq!(repo.get(""), Some(a));
+            assert_eq!(
+                repo.allocated_memory(),
+                CHUNK_DEFAULT_CAPACITY + size_of::<usize>() * ENTRIES_INITIAL_CAPACITY
+            );
+
+            // neighbours are not mistaken for the empty string
+            let x = ScopedSto::intern_in("x", repo);
+            assert_eq!(x, "x");
+            assert_eq!(ScopedSto::intern_in("", repo), a);
+            assert_eq!(a.as_str(), "");
+            assert_eq!(repo.len(), 2);
+        }
+        assert_eq!(
+            unsafe { ScopedSto::intern_in("", &repos[2]).as_str_with_sentinel() },
+            b"\0"
+        );
+    }
+
+    #[test]
+    fn test_into_owned() {
+        let repo = Repository::new();
+        let hello = ScopedSto::intern_in("hello", &repo);
+        let string: String = hello.into();
+        let boxed: Box<str> = hello.into();
+        drop(repo);
+        assert_eq!(string, "hello");
+        assert_eq!(&*boxed, "hello");
+    }
+
+    #[test]
+    fn test_merge() {
+        let a = Repository::new();
+        let b = Repository::with_seed([1, 2, 3, 4]);
+        let a_stos = (0..6000)
+            .map(|i| ScopedSto::intern_in(i.to_string(), &a))
+            .collect::<Vec<_>>();
+        let b_stos = (4000..10_000)
+            .map(|i| ScopedSto::intern_in(i.to_string(), &b))
+            .collect::<Vec<_>>();
+        b.intern_bytes_in(b"\xFF");
+
+        assert_eq!(a.merge(&b), 4001);
+        assert_eq!(a.len(), 10_000);
+        for sto in a_stos.iter().chain(&b_stos) {
+            let merged = a.get(sto.as_str()).unwrap();
+            assert_eq!(merged.as_str(), sto.as_str());
+        }
+        assert_eq!(a.get("4000"), Some(a_stos[4000]));
+        assert_eq!(a.intern_bytes_in(b"\xFF").as_bytes(), b"\xFF");
+        assert_eq!(a.merge(&b), 0);
+
+        // merging into itself changes nothing
+        assert_eq!(b.merge(&b), 0);
+        assert_eq!(b.len(), 6000);
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let mut repo = (0..10_000)
+            .map(|i| (i % 5000).to_string())
+            .collect::<Repository>();
+        assert_eq!(repo.len(), 5000);
+
+        repo.extend((0..10_000).map(|i| i.to_string()));
+        assert_eq!(repo.len(), 10_000);
+        (&repo).extend(["hello", "world", "hello"]);
+        assert_eq!(repo.len(), 10_002);
+        assert!(repo.contains("9999") && repo.contains("world"));
+    }
+
+    #[test]
+    fn test_intern_many() {
+        let repo = Repository::builder().indexed().build();
+        let strings = (0..10_000)
+            .map(|i| (i % 7000).to_string())
+            .collect::<Vec<_>>();
+        let strings = strings.iter().map(String::as_str).collect::<Vec<_>>();
+
+        let mut stos = vec![ScopedSto::intern_in("first", &repo)];
+        repo.intern_many(&strings, &mut stos);
+        assert_eq!(stos.len(), 10_001);
+        assert_eq!(stos[0], "first");
+        for (sto, string) in stos[1..].iter().zip(&strings) {
+            assert_eq!(sto, string);
+            assert_eq!(*sto, ScopedSto::intern_in(string, &repo));
+        }
+        assert_eq!(repo.len(), 7001);
+        assert_eq!(repo.export_table().len(), 7001);
+
+        repo.intern_many(&[], &mut stos);
+        assert_eq!(stos.len(), 10_001);
+    }
+
+    #[test]
+    fn test_try_lock_intern_in() {
+        let repo = Repository::new();
+        for i in 0..10_000 {
+            let s = i.to_string();
+            assert_eq!(repo.try_lock_intern_in(&s).unwrap(), s);
+        }
+        assert_eq!(repo.len(), 10_000);
+
+        // the only bucket is held by the iterator
+        let repo = Repository::<1>::with_buckets();
+        let hello = ScopedSto::intern_in("hello", &repo);
+        let mut iter = repo.iter();
+        assert_eq!(iter.next(), Some(hello));
+        #[cfg(not(feature = "rwlock"))]
+        assert_eq!(repo.try_lock_intern_in("hello"), None);
+        #[cfg(feature = "rwlock")]
+        assert_eq!(repo.try_lock_intern_in("hello"), Some(hello));
+        assert_eq!(repo.try_lock_intern_in("world"), None);
+        drop(iter);
+        assert_eq!(repo.try_lock_intern_in("world").unwrap(), "world");
+    }
+
+    #[test]
+    fn test_intern_in_tracked() {
+        let repo = Repository::new();
+        let mut tracked = 0;
+        for i in 0..10_000 {
+            let (sto, allocated) = repo.intern_in_tracked(&i.to_string());
+            assert_eq!(sto, i.to_string());
+            tracked += allocated;
+            assert_eq!(tracked, repo.allocated_memory());
+        }
+        let large = "x".repeat(CHUNK_DEFAULT_CAPACITY);
+        let (_, allocated) = repo.intern_in_tracked(&large);
+        assert!(allocated > CHUNK_DEFAULT_CAPACITY);
+        assert_eq!(repo.intern_in_tracked(&large).1, 0);
+        assert_eq!(repo.intern_in_tracked("42").1, 0);
+    }
+
+    #[test]
+    fn test_bucket_stats() {
+        let repo = Repository::<4>::with_buckets();
+        assert!(repo.bucket_stats().iter().enumerate().all(|(i, s)| *s
+            == BucketStat {
+                index: i,
+                ..Default::default()
+            }));
+
+        (0..10_000).for_each(|i| {
+            ScopedSto::intern_in(i.to_string(), &repo);
+        });
+        let stats = repo.bucket_stats();
+        assert_eq!(stats.len(), 4);
+        assert_eq!(stats.iter().map(|s| s.entry_count).sum::<usize>(), 10_000);
+        assert_eq!(
+            stats.iter().map(|s| s.arena_bytes).sum::<usize>(),
+            repo.memory_stats().arena_bytes
+        );
+        for stat in &stats {
+            assert!(stat.entry_count > 2000);
+            assert!(stat.capacity.is_power_of_two() && stat.capacity > stat.entry_count);
+        }
+    }
+
+    #[test]
+    fn test_load_factor() {
+        for (num, den) in [(1, 2), (3, 4), (7, 8), (1, 255), (254, 255)] {
+            let mut repo = Repository::builder()
+                .load_factor(num, den)
+                .build_sharded::<4, _>(ahash::RandomState::new());
+            for round in 0..2 {
+                (0..10_000).for_each(|i| {
+                    ScopedSto::intern_in(i.to_string(), &repo);
+                });
+                assert_eq!(repo.len(), 10_000);
+                let limit = num as f64 / den as f64;
+                assert!(repo.load_factor() <= limit);
+                for stat in repo.bucket_stats() {
+                    assert!(stat.entry_count * den as usize <= stat.capacity * num as usize);
+                }
+                if round == 0 {
+                    repo.shrink_to_fit();
+                    assert!(repo.load_factor() <= limit);
+                    repo.clear();
+                    assert_eq!(repo.load_factor(), 0.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_load_factor_too_large() {
+        Repository::with_load_factor(4, 4);
+    }
+
+    #[test]
+    fn test_eq_str() {
+        let repo = Repository::new();
+        let sto = ScopedSto::intern_in("hello", &repo);
+        let boxed: Box<str> = "hello".into();
+        assert!(sto == *boxed);
+        assert!(*boxed == sto);
+        assert!("hello" == sto);
+        assert!(sto != *"world");
+        assert!("world" != sto);
+        let (hello, world) = (String::from("hello"), String::from("world"));
+        assert!(hello == sto);
+        assert!(sto == hello);
+        assert!(world != sto);
+    }
+
+    #[test]
+    fn test_cmp_str() {
+        let repo = Repository::new();
+        let strings = ["", "a", "b", "ab", "Z", "é", "ë", "\u{10000}", "\u{ffff}"];
+        for x in strings {
+            let sto = ScopedSto::intern_in(x, &repo);
+            for y in strings {
+                let owned = String::from(y);
+                assert_eq!(sto.partial_cmp(&y), Some(x.cmp(y)));
+                assert_eq!(sto.partial_cmp(&owned), Some(x.cmp(y)));
+                assert_eq!(y.partial_cmp(&sto), Some(y.cmp(x)));
+                assert_eq!(owned.partial_cmp(&sto), Some(y.cmp(x)));
+            }
+        }
+        let mut mixed = vec![String::from("b"), String::from("c")];
+        mixed.retain(|s| *s > ScopedSto::intern_in("b", &repo));
+        assert_eq!(mixed, ["c"]);
+        assert!(ScopedSto::intern_in("a", &repo) < "b");
+    }
+
+    #[test]
+    fn test_intern_static() {
+        static LONG: &str = "a long literal which is not copied into the repository";
+
+        let repo = Repository::new();
+        let borrowed = repo.intern_static(LONG);
+        assert_eq!(borrowed.as_str().as_ptr(), LONG.as_ptr());
+        assert_eq!(
+            borrowed.parts(),
+            (borrowed.hash(), LONG.len(), LONG.as_ptr())
+        );
+        assert_eq!(borrowed, ScopedSto::intern_in(String::from(LONG), &repo));
+        assert!(!repo.is_interned_ptr(borrowed.as_str()));
+        assert!(repo.memory_stats().arena_bytes - repo.memory_stats().wasted_bytes < LONG.len());
+
+        let copied = ScopedSto::intern_in("hello", &repo);
+        assert_eq!(repo.intern_static("hello"), copied);
+        assert_eq!(repo.intern_static(""), "");
+
+        let copy = repo.clone_contents();
+        assert!(copy.is_interned_ptr(copy.get(LONG).unwrap().as_str()));
+
+        let sentinel = Repository::with_sentinel(b'\0');
+        let s = sentinel.intern_static(LONG);
+        assert_ne!(s.as_str().as_ptr(), LONG.as_ptr());
+        assert_eq!(unsafe { s.as_str_with_sentinel() }.last(), Some(&0));
+    }
+
+    #[test]
+    fn test_intern_concat() {
+        let repo = Repository::new();
+        let hello = ScopedSto::intern_in("hello", &repo);
+        assert_eq!(repo.intern_concat("hel", "lo"), hello);
+        assert_eq!(repo.intern_concat("", "hello"), hello);
+        assert_eq!(repo.intern_concat("hello", ""), hello);
+        assert_eq!(repo.intern_concat("", ""), "");
+
+        let concat = repo.intern_concat("wor", "ld");
+        assert_eq!(concat, ScopedSto::intern_in("world", &repo));
+        assert_eq!(repo.len(), 3);
+
+        let long = "x".repeat(100_000);
+        assert_eq!(repo.intern_concat(&long, "é").len(), 100_002);
+    }
+
+    #[test]
+    fn test_with_capacity() {
+        let repo = Repository::with_capacity(0);
+        assert_eq!(repo.allocated_memory(), 0);
+
+        let repo = Repository::with_capacity(1);
+        assert!(repo
+            .bucket_stats()
+            .iter()
+            .all(|s| s.capacity == ENTRIES_MIN_CAPACITY));
+        assert_eq!(
+            repo.allocated_memory(),
+            BUCKET_NUMBER * ENTRIES_MIN_CAPACITY * size_of::<usize>()
+        );
+        assert!(!repo.intern_grow_aware("hello").1);
+
+        let repo = Repository::with_capacity(1000);
+        assert!(repo.bucket_stats().iter().all(|s| s.capacity == 32));
+
+        let repo = Repository::with_capacity(200_000);
+        let entries_bytes = repo.memory_stats().entries_bytes;
+        let grows = (0..200_000)
+            .filter(|i| repo.intern_grow_aware(i.to_string()).1)
+            .count();
+        assert_eq!(grows, 0);
+        assert_eq!(repo.memory_stats().entries_bytes, entries_bytes);
+        assert!(repo
+            .bucket_stats()
+            .iter()
+            .all(|s| s.capacity == ENTRIES_INITIAL_CAPACITY * 8));
+
+        let repo = Repository::new();
+        let grows = (0..200_000)
+            .filter(|i| repo.intern_grow_aware(i.to_string()).1)
+            .count();
+        assert!(grows >= BUCKET_NUMBER * 3);
+    }
+
+    #[test]
+    fn test_reserve() {
+        let mut repo = Repository::<4>::with_buckets();
+        repo.reserve(0);
+        assert_eq!(repo.allocated_memory(), 0);
+
+        // a few strings do not allocate tables of the initial capacity
+        repo.reserve(1);
+        assert!(repo
+            .bucket_stats()
+            .iter()
+            .all(|s| s.capacity == ENTRIES_MIN_CAPACITY));
+        repo.reserve(4 * 6);
+        assert!(repo
+            .bucket_stats()
+            .iter()
+            .all(|s| s.capacity == ENTRIES_MIN_CAPACITY));
+        repo.reserve(4 * 7);
+        assert!(repo.bucket_stats().iter().all(|s| s.capacity == 16));
+
+        (0..10_000).for_each(|i| {
+            ScopedSto::intern_in(i.to_string(), &repo);
+        });
+        let hello = ScopedSto::intern_in("hello", &repo).as_str().as_ptr();
+        repo.reserve(100_000);
+        assert_eq!(repo.len(), 10_001);
+        assert_eq!(repo.get("hello").unwrap().as_str().as_ptr(), hello);
+        let grows = (10_000..110_000)
+            .filter(|i| repo.intern_grow_aware(i.to_string()).1)
+            .count();
+        assert_eq!(grows, 0);
+
+        let capacity = repo.memory_stats().entries_bytes;
+        repo.reserve(1);
+        assert_eq!(repo.memory_stats().entries_bytes, capacity);
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut repo = Repository::new();
+        assert_eq!(repo.capacity(), 0);
+        ScopedSto::intern_in("hello", &repo);
+        assert_eq!(repo.capacity(), ENTRIES_INITIAL_CAPACITY);
+
+        (0..100_000).for_each(|i| {
+            ScopedSto::intern_in(i.to_string(), &repo);
+        });
+        let capacity = repo.capacity();
+        assert_eq!(
+            capacity,
+            repo.bucket_stats()
+                .iter()
+                .map(|s| s.capacity)
+                .sum::<usize>()
+        );
+        assert!(capacity > repo.len());
+
+        repo.retain(|s| s.len() < 3);
+        repo.shrink_to_fit();
+        assert!(repo.capacity() < capacity);
+        repo.clear();
+        assert_eq!(repo.capacity(), 0);
+    }
+
+    #[test]
+    fn test_collect() {
+        let mut repo = Repository::builder().indexed().build();
+        (0..20_000).for_each(|i| {
+            ScopedSto::intern_in(i.to_string(), &repo);
+        });
+        repo.intern_bytes_in(b"\xff");
+        let before = repo.allocated_memory();
+
+        let live = (0..20_000).step_by(1000).map(|i| i.to_string());
+        let freed = repo.collect(live.chain(["absent".to_string(), "1000".to_string()]));
+        assert_eq!(freed, before - repo.allocated_memory());
+        assert!(freed > before / 2);
+        assert_eq!(repo.len(), 20);
+        assert!(repo.get("absent").is_none());
+        assert!(repo.get("1").is_none());
+        assert_eq!(repo.intern_bytes_in(b"\xff").as_bytes(), b"\xff");
+
+        // ids keep the order of interning
+        let ids = (0..20)
+            .map(|i| repo.resolve(i).unwrap())
+            .collect::<Vec<_>>();
+        let expected = (0..20_000).step_by(1000).map(|i| i.to_string());
+        assert!(ids.iter().map(|s| s.as_str()).eq(expected));
+        assert_eq!(ids[1], ScopedSto::intern_in("1000", &repo));
+        assert!(repo.is_interned_ptr(ids[1].as_str()));
+
+        let mut repo = Repository::new();
+        ScopedSto::intern_in("hello", &repo);
+        repo.collect(None::<&str>);
+        assert!(repo.is_empty());
+    }
+
+    #[test]
+    fn test_into_strings() {
+        let repo = Repository::new();
+        (0..10_000).for_each(|i| {
+            ScopedSto::intern_in(i.to_string(), &repo);
+        });
+        repo.intern_bytes_in(b"\xff");
+
+        let mut strings = repo.into_strings();
+        strings.sort_unstable_by_key(|s| s.parse::<u32>().unwrap());
+        assert!(strings.into_iter().eq((0..10_000).map(|i| i.to_string())));
+        assert!(Repository::new().into_strings().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_save_to_load_from() {
+        let repo = Repository::new();
+        let strings = (0..10_000).map(|i| i.to_string()).collect::<Vec<_>>();
+        for s in &strings {
+            ScopedSto::intern_in(s, &repo);
+        }
+        ScopedSto::intern_in("", &repo);
+        let mut snapshot = Vec::new();
+        repo.save_to(&mut snapshot).unwrap();
+
+        let restored = Repository::load_from(&mut snapshot.as_slice()).unwrap();
+        assert_eq!(restored.len(), 10_001);
+        for s in &strings {
+            let sto = restored.get(s).unwrap();
+            assert_eq!(sto.hash(), repo.hash_of(s));
+            assert_eq!(ScopedSto::intern_in(s, &restored), sto);
+        }
+        assert_eq!(restored.get("").unwrap(), "");
+
+        let invalid = |snapshot: &[u8]| {
+            Repository::load_from(&mut &snapshot[..])
+                .unwrap_err()
+                .kind()
+        };
+        assert_eq!(
+            invalid(&snapshot[..snapshot.len() - 1]),
+            std::io::ErrorKind::UnexpectedEof
+        );
+        assert_eq!(invalid(b"not a snapshot"), std::io::ErrorKind::InvalidData);
+
+        // the hash of the first entry follows the magic, buckets, fingerprint and count
+        let mut corrupted = snapshot.clone();
+        corrupted[24] ^= 1;
+        assert_eq!(invalid(&corrupted), std::io::ErrorKind::InvalidData);
+
+        let mut seeded = Vec::new();
+        Repository::with_seed([1, 2, 3, 4])
+            .save_to(&mut seeded)
+            .unwrap();
+        assert_eq!(invalid(&seeded), std::io::ErrorKind::InvalidData);
+        let mut fewer = Vec::new();
+        Repository::<16>::with_buckets()
+            .save_to(&mut fewer)
+            .unwrap();
+        assert_eq!(invalid(&fewer), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_intern_substr() {
+        let repo = Repository::new();
+        let other = Repository::new();
+        let line = ScopedSto::intern_in("key = välue", &repo);
+        let key = ScopedSto::intern_in("key", &repo);
+        assert_eq!(repo.intern_substr(line, 0..3), key);
+        assert_eq!(repo.intern_substr(line, 6..12), "välue");
+        assert_eq!(repo.intern_substr(line, 3..3), "");
+        assert_eq!(repo.intern_substr(line, 0..12), line);
+        assert_eq!(other.intern_substr(line, 0..3), "key");
+        assert_eq!(repo.len(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not a char boundary")]
+    fn test_intern_substr_not_char_boundary() {
+        let repo = Repository::new();
+        let line = ScopedSto::intern_in("välue", &repo);
+        repo.intern_substr(line, 0..2);
+    }
+
+    #[test]
+    fn test_str_predicates() {
+        fn check<'a, S: Into<ScopedSto<'a>>>(s: S) -> bool {
+            let s = s.into();
+            s.starts_with("ab") && s.ends_with("yz") && s.contains("mn")
+        }
+
+        let repo = Repository::new();
+        let s = ScopedSto::intern_in("ab-mn-yz", &repo);
+        assert!(check(s));
+        assert!(!check(ScopedSto::intern_in("ab-yz", &repo)));
+        assert!(s.starts_with("") && s.ends_with("") && s.contains(""));
+        assert!(!s.starts_with("b") && !s.ends_with("y") && !s.contains("nm"));
+    }
+
+    #[test]
+    fn test_raw() {
+        let repo = Repository::new();
+        let stos = (0..1000)
+            .map(|i| ScopedSto::intern_in(i.to_string(), &repo))
+            .collect::<Vec<_>>();
+        let raws = stos.iter().map(ScopedSto::as_raw).collect::<Vec<_>>();
+        for (i, (&raw, sto)) in raws.iter().zip(&stos).enumerate() {
+            let restored = unsafe { ScopedSto::from_raw(raw) };
+            assert_eq!(restored, *sto);
+            assert_eq!(restored, i.to_string());
+            assert_eq!(restored.hash(), sto.hash());
+        }
+
+        let borrowed = repo.intern_static("a static string");
+        assert_eq!(unsafe { ScopedSto::from_raw(borrowed.as_raw()) }, borrowed);
+    }
+
+    #[test]
+    fn test_id() {
+        let repo = Repository::builder().indexed().sentinel(b'\0').build();
+        let large = "x".repeat(CHUNK_DEFAULT_CAPACITY);
+        let strings = (0..5000)
+            .map(|i| i.to_string())
+            .chain(["".to_string(), large])
+            .collect::<Vec<_>>();
+        for (id, s) in strings.iter().enumerate() {
+            let sto = ScopedSto::intern_in(s, &repo);
+            assert_eq!(sto.id(), Some(id as u32));
+            assert_eq!(sto, s.as_str());
+            assert_eq!(unsafe { sto.as_str_with_sentinel() }.len(), s.len() + 1);
+        }
+        for (id, s) in strings.iter().enumerate() {
+            assert_eq!(ScopedSto::intern_in(s, &repo).id(), Some(id as u32));
+        }
+
+        let repo = Repository::builder().indexed().build();
+        let hello = repo.intern_static("a static string");
+        assert_eq!(hello.id(), Some(0));
+        assert_eq!(hello, "a static string");
+        assert_eq!(ScopedSto::intern_in("hello", &Repository::new()).id(), None);
+    }
+
+    #[test]
+    fn test_hash32() {
+        let repo = Repository::new();
+        let stos = (0..10_000)
+            .map(|i| ScopedSto::intern_in(i.to_string(), &repo))
+            .collect::<Vec<_>>();
+        for sto in &stos {
+            let hash = sto.hash();
+            assert_eq!(sto.hash32(), (hash as u32) ^ ((hash >> 32) as u32));
+        }
+        let distinct = stos
+            .iter()
+            .map(ScopedSto::hash32)
+            .collect::<std::collections::HashSet<_>>();
+        assert!(distinct.len() > 9_900);
+    }
+
+    #[test]
+    fn test_intern_tiered() {
+        let hot = Repository::new();
+        let cold = Repository::<16>::with_buckets();
+        let colds = (0..1000)
+            .map(|i| ScopedSto::intern_in(i.to_string(), &cold))
+            .collect::<Vec<_>>();
+
+        // hits in the cold repository are copied into the hot one
+        for (i, sto) in colds.iter().enumerate() {
+            let s = i.to_string();
+            let tiered = hot.intern_tiered(&s, &cold);
+            assert!(tiered.ptr_eq(&hot.get(&s).unwrap()));
+            assert!(hot.is_interned_ptr(tiered.as_str()));
+            assert_eq!(tiered.as_str(), sto.as_str());
+            assert_ne!(tiered.as_str().as_ptr(), sto.as_str().as_ptr());
+        }
+        assert_eq!(hot.len(), 1000);
+        assert_eq!(cold.len(), 1000);
+
+        // misses in both are interned in the hot repository only
+        let miss = hot.intern_tiered("miss", &cold);
+        assert!(miss.ptr_eq(&hot.get("miss").unwrap()));
+        assert_eq!(hot.len(), 1001);
+        assert!(cold.get("miss").is_none());
+
+        // hits in the hot repository are returned as is
+        let shadowed = ScopedSto::intern_in("42", &hot);
+        assert!(hot.intern_tiered("42", &cold).ptr_eq(&shadowed));
+        assert!(hot.intern_tiered("7", &hot).ptr_eq(&hot.get("7").unwrap()));
+        assert_eq!(hot.len(), 1001);
+    }
+
+    #[test]
+    fn test_intern_pair() {
+        assert_eq!(size_of::<ScopedPair>(), size_of::<usize>());
+        assert_eq!(size_of::<Option<ScopedPair>>(), size_of::<usize>());
+
+        let mut repo = Repository::new();
+        let pairs = (0..1000)
+            .map(|i| repo.intern_pair(i.to_string(), "name"))
+            .collect::<Vec<_>>();
+        let memory = repo.allocated_memory();
+        for (i, pair) in pairs.iter().enumerate() {
+            let again = repo.intern_pair(i.to_string(), "name");
+            assert_eq!(again, *pair);
+            assert_eq!(again.first(), ScopedSto::intern_in(i.to_string(), &repo));
+            assert_eq!(again.second(), "name");
+        }
+        assert_eq!(repo.allocated_memory(), memory);
+        assert_eq!(repo.len(), 1001);
+        assert_ne!(repo.intern_pair("name", "0"), pairs[0]);
+        assert!(pairs[0] < pairs[1]);
+        assert_eq!(format!("{:?}", pairs[0]), "(0, name)");
+
+        let copy = repo.clone_contents();
+        let memory = copy.allocated_memory();
+        assert_eq!(copy.intern_pair("1", "name").first(), "1");
+        assert_eq!(copy.allocated_memory(), memory);
+
+        // pairs follow their components when they are moved, or are dropped with them
+        repo.retain(|s| s.len() > 2);
+        let entry_count = repo.memory_stats().entry_count;
+        assert_eq!(entry_count, repo.len() + 900);
+        let pair = repo.intern_pair("100", "name");
+        assert_eq!(pair.first(), "100");
+        assert_eq!(pair.second(), "name");
+        assert_eq!(repo.memory_stats().entry_count, entry_count);
+
+        repo.clear();
+        assert_eq!(repo.allocated_memory(), 0);
+    }
+
+    #[test]
+    fn test_clone_contents() {
+        let repo = Repository::builder().max_probe_length(4).build();
+        (0..10_000).for_each(|i| {
+            ScopedSto::intern_in(i.to_string(), &repo);
+        });
+        let bytes = repo.intern_bytes_in(b"\xFFhello");
+
+        let copy = repo.clone_contents();
+        let original = repo.to_sorted_vec();
+        let copied = copy.to_sorted_vec();
+        assert_eq!(original.len(), copied.len());
+        for (a, b) in original.iter().zip(copied.iter()) {
+            assert_eq!(a.as_str(), b.as_str());
+            assert!(!copy.is_interned_ptr(a.as_str()));
+        }
+
+        // bytes are copied too
+        let memory = copy.allocated_memory();
+        let copied = copy.intern_bytes_in(b"\xFFhello");
+        assert_eq!(copy.allocated_memory(), memory);
+        assert_eq!(copied.as_bytes(), bytes.as_bytes());
+        assert_ne!(copied.as_bytes().as_ptr(), bytes.as_bytes().as_ptr());
+
+        drop(repo);
+        assert_eq!(ScopedSto::intern_in("42", &copy), "42");
+    }
+
+    #[test]
+    fn test_string_alignment() {
+        for alignment in [1, 8, 16, 64] {
+            let repo = Repository::with_string_alignment(alignment);
+            let strings = (0..2000)
+                .map(|i| "x".repeat(i % 100))
+                .chain(Some("test".repeat(CHUNK_DEFAULT_CAPACITY)))
+                .collect::<Vec<_>>();
+            for s in &strings {
+                let a = ScopedSto::intern_in(s, &repo);
+                assert_eq!(a, *s);
+                assert_eq!(a.as_str().as_ptr() as usize % alignment, 0);
+            }
+            for s in &strings {
+                assert_eq!(ScopedSto::intern_in(s, &repo), *s);
+            }
+        }
+    }
+
+    #[test]
+    fn test_intern_folding() {
+        let repo = Repository::new();
+        let count_l = |acc: usize, b: u8| acc + usize::from(b == b'l');
+        let large_string = "hello".repeat(CHUNK_DEFAULT_CAPACITY);
+        for s in ["hello", "", &large_string] {
+            let (a, folded) = repo.intern_folding(s, 0, count_l);
+            assert_eq!(a, s);
+            assert_eq!(folded, s.bytes().fold(0, count_l));
+            // hit
+            let (b, folded) = repo.intern_folding(s, 0, count_l);
+            assert_eq!(a, b);
+            assert_eq!(folded, s.bytes().fold(0, count_l));
+        }
+    }
+
+    #[test]
+    fn test_shrink_bucket() {
+        let mut repo = Repository::new();
+        (0..100_000).for_each(|i| {
+            ScopedSto::intern_in(i.to_string(), &repo);
+        });
+        repo.retain(|s| s.len() < 3);
+
+        let before = repo.allocated_memory();
+        for bucket in 0..BUCKET_NUMBER {
+            repo.shrink_bucket(bucket);
+        }
+        assert!(repo.allocated_memory() < before);
+
+        let retained = repo.to_sorted_vec();
+        assert_eq!(retained.len(), 100);
+        for s in (0..100_000).map(|i| i.to_string()) {
+            assert_eq!(ScopedSto::intern_in(&s, &repo), s);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_shrink_bucket_out_of_range() {
+        Repository::new().shrink_bucket(BUCKET_NUMBER);
+    }
+
+    #[test]
+    fn test_promote_local() {
+        let local = LocalRepository::new();
+        let words = (0..5000).map(|i| i.to_string()).collect::<Vec<_>>();
+        for word in &words {
+            local.intern(word);
+        }
+        assert_eq!(local.intern("42"), local.intern("42"));
+        assert!(local.get("hello").is_none());
+
+        let repo = Repository::new();
+        let promoted = repo.promote_local(&local);
+        assert_eq!(promoted.len(), words.len());
+        for (local_sto, sto) in promoted {
+            assert_eq!(local_sto.as_str(), sto.as_str());
+            assert_eq!(sto, ScopedSto::intern_in(local_sto.as_str(), &repo));
+        }
+    }
+
+    #[test]
+    fn test_sentinel() {
+        for alignment in [8, 32] {
+            let repo = Repository::builder()
+                .string_alignment(alignment)
+                .sentinel(0xFF)
+                .build();
+            let large = "x".repeat(CHUNK_DEFAULT_CAPACITY);
+            let strings = (0..3000)
+                .map(|i| i.to_string())
+                .chain(["".to_string(), large])
+                .collect::<Vec<_>>();
+            let stos = strings
+                .iter()
+                .map(|s| ScopedSto::intern_in(s, &repo))
+                .collect::<Vec<_>>();
+            for (s, sto) in strings.iter().zip(stos) {
+                assert_eq!(sto.as_str(), s);
+                let bytes = unsafe { sto.as_str_with_sentinel() };
+                assert_eq!(&bytes[..s.len()], s.as_bytes());
+                assert_eq!(bytes[s.len()], 0xFF);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_intern_all_grouped() {
+        let repo = Repository::new();
+        let strings = (0..1000).map(|i| (i % 37).to_string()).collect::<Vec<_>>();
+        let strings = strings.iter().map(String::as_str).collect::<Vec<_>>();
+        let (stos, groups) = repo.intern_all_grouped(&strings);
+        assert_eq!(groups.len(), 37);
+
+        let mut indices = groups.iter().flatten().copied().collect::<Vec<_>>();
+        indices.sort_unstable();
+        assert!(indices.into_iter().eq(0..strings.len()));
+        for group in groups {
+            assert!(group.iter().all(|&i| stos[i] == stos[group[0]]));
+            assert!(group.iter().all(|&i| stos[i].as_str() == strings[i]));
+        }
+    }
+
+    #[test]
+    fn test_intern_numeric_canonical() {
+        let repo = Repository::new();
+        for (input, expected) in [
+            ("7", "7"),
+            ("007", "7"),
+            ("+7", "7"),
+            ("-7", "-7"),
+            ("-007", "-7"),
+            ("0", "0"),
+            ("000", "0"),
+            ("-0", "0"),
+            ("+00", "0"),
+            (
+                "123456789012345678901234567890",
+                "123456789012345678901234567890",
+            ),
+            ("", ""),
+            ("-", "-"),
+            ("+", "+"),
+            ("7.0", "7.0"),
+            ("--7", "--7"),
+            ("0x7", "0x7"),
+            ("٧", "٧"),
+        ] {
+            let sto = repo.intern_numeric_canonical(input);
+            assert_eq!(sto.as_str(), expected);
+            assert_eq!(sto, ScopedSto::intern_in(expected, &repo));
+        }
+    }
+
+    #[test]
+    fn test_fork() {
+        let repo = Repository::new();
+        let base = (0..1000)
+            .map(|i| ScopedSto::intern_in(i.to_string(), &repo))
+            .collect::<Vec<_>>();
+        let allocated = repo.allocated_memory();
+
+        let fork = repo.fork();
+        for (i, &sto) in base.iter().enumerate() {
+            assert_eq!(fork.intern(i.to_string()), sto);
+        }
+        assert_eq!(fork.allocated_memory(), 0);
+
+        let new = (1000..2000)
+            .map(|i| fork.intern(i.to_string()))
+            .collect::<Vec<_>>();
+        for (i, &sto) in (1000..2000).zip(&new) {
+            assert_eq!(fork.get(&i.to_string()), Some(sto));
+            assert!(repo.find(&i.to_string()).is_none());
+        }
+        assert_eq!(repo.allocated_memory(), allocated);
+
+        drop(fork);
+        assert_eq!(base[42], "42");
+    }
+
+    #[test]
+    fn test_export_table() {
+        let repo = Repository::builder().indexed().build();
+        let strings = (0..5000).map(|i| i.to_string()).collect::<Vec<_>>();
+        for s in strings.iter().chain(&strings) {
+            ScopedSto::intern_in(s, &repo);
+        }
+        let table = repo.export_table();
+        assert_eq!(table.len(), strings.len());
+        for ((id, s), expected) in table.into_iter().zip(&strings) {
+            assert_eq!(s, expected);
+            assert_eq!(repo.resolve(id).unwrap(), expected.as_str());
+            assert_eq!(repo.resolve(id).unwrap().id(), Some(id));
+        }
+
+        let mut repo = repo;
+        repo.retain(|s| s.len() < 4);
+        let table = repo.export_table();
+        assert_eq!(table.len(), 1000);
+        for ((id, s), expected) in table.into_iter().zip(&strings) {
+            assert_eq!(
+                (id as usize, s),
+                (expected.parse().unwrap(), expected.as_str())
+            );
+            assert_eq!(repo.get(s).unwrap().id(), Some(id));
+        }
+        assert!(repo.resolve(1000).is_none());
+
+        assert!(Repository::new().export_table().is_empty());
+    }
+
+    #[test]
+    fn test_initial_buffer() {
+        let buf = Box::leak(vec![0; BUCKET_NUMBER * 1024 + 3].into_boxed_slice());
+        let range = buf.as_ptr_range();
+        let repo = Repository::with_initial_buffer(&mut buf[3..]);
+
+        let stos = (0..10_000)
+            .map(|i| ScopedSto::intern_in(i.to_string(), &repo))
+            .collect::<Vec<_>>();
+        let in_buf = stos
+            .iter()
+            .filter(|s| range.contains(&s.as_str().as_ptr()))
+            .count();
+        assert!(in_buf > 0);
+        assert!(in_buf < stos.len());
+        for (i, sto) in stos.iter().enumerate() {
+            assert_eq!(sto.as_str(), i.to_string());
+        }
+        drop(repo);
+
+        let tiny = Repository::with_initial_buffer(Box::leak(vec![0; 10].into_boxed_slice()));
+        assert_eq!(ScopedSto::intern_in("hello", &tiny), "hello");
+    }
+
+    #[test]
+    fn test_intern_batch_sorted() {
+        let repo = Repository::new();
+        let strings = (0..5000)
+            .map(|i| (i * 7 % 1000).to_string())
+            .collect::<Vec<_>>();
+        let strings = strings.iter().map(String::as_str).collect::<Vec<_>>();
+        let stos = repo.intern_batch_sorted(&strings);
+        assert_eq!(stos, strings);
+        for (&s, sto) in strings.iter().zip(stos) {
+            assert_eq!(sto, ScopedSto::intern_in(s, &repo));
+        }
+    }
+
+    #[test]
+    fn test_intern_validated() {
+        let repo = Repository::new();
+        let checked = std::cell::Cell::new(0);
+        let reject_long = |s: &str| {
+            checked.set(checked.get() + 1);
+            if s.len() > 3 {
+                Err(s.len())
+            } else {
+                Ok(())
+            }
+        };
+        assert_eq!(repo.intern_validated("abcd", reject_long), Err(4));
+        assert!(repo.find("abcd").is_none());
+
+        let abc = repo.intern_validated("abc", reject_long).unwrap();
+        assert_eq!(abc, ScopedSto::intern_in("abc", &repo));
+        assert_eq!(checked.get(), 2);
+
+        ScopedSto::intern_in("long", &repo);
+        assert_eq!(repo.intern_validated("abc", reject_long), Ok(abc));
+        assert_eq!(repo.intern_validated("long", reject_long).unwrap(), "long");
+        assert_eq!(checked.get(), 2);
+    }
+
+    #[test]
+    fn test_intern_unique() {
+        let repo = Repository::new();
+        let strings = (0..100).map(|i| i.to_string()).collect::<Vec<_>>();
+        let succeeded = std::thread::scope(|scope| {
+            let handles = (0..4)
+                .map(|_| {
+                    scope.spawn(|| {
+                        strings
+                            .iter()
+                            .filter_map(|s| repo.intern_unique(s).ok())
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(succeeded.len(), strings.len());
+
+        let err = repo.intern_unique("42").unwrap_err();
+        assert_eq!(err.existing(), ScopedSto::intern_in("42", &repo));
+        assert_eq!(err.to_string(), "string \"42\" is already interned");
+    }
+
+    #[test]
+    fn test_get() {
+        let repo = Repository::new();
+        assert_eq!(repo.get("hello"), None);
+        assert_eq!(repo.allocated_memory(), 0);
+
+        let hello = ScopedSto::intern_in("hello", &repo);
+        let allocated = repo.allocated_memory();
+        for i in 0..1000 {
+            assert_eq!(repo.get(&i.to_string()), None);
+        }
+        assert_eq!(repo.allocated_memory(), allocated);
+        assert_eq!(repo.get("hello"), Some(hello));
+    }
+
+    #[test]
+    fn test_intern_in_full() {
+        let repo = Repository::new();
+        for i in 0..2000 {
+            let (sto, inserted) = ScopedSto::intern_in_full(i.to_string(), &repo);
+            assert!(inserted);
+            assert_eq!(ScopedSto::intern_in_full(sto, &repo), (sto, false));
+        }
+    }
+
+    #[test]
+    fn test_len() {
+        let mut repo = Repository::new();
+        assert!(repo.is_empty());
+        for i in 0..10_000 {
+            ScopedSto::intern_in(i.to_string(), &repo);
+            ScopedSto::intern_in((i / 2).to_string(), &repo);
+        }
+        assert_eq!(repo.len(), 10_000);
+        assert!(!repo.is_empty());
+
+        repo.retain(|s| s.len() < 4);
+        assert_eq!(repo.len(), 1000);
+        repo.retain(|_| false);
+        assert!(repo.is_empty());
+    }
+
+    #[test]
+    fn test_iter() {
+        let repo = Repository::new();
+        assert_eq!(repo.iter().count(), 0);
+
+        let stos = (0..10_000)
+            .map(|i| ScopedSto::intern_in(i.to_string(), &repo))
+            .collect::<Vec<_>>();
+        let mut iterated = (&repo).into_iter().collect::<Vec<_>>();
+        assert_eq!(iterated.len(), stos.len());
+        iterated.sort_unstable_by_key(|s| s.as_str().parse::<usize>().unwrap());
+        assert_eq!(iterated, stos);
+    }
+
+    #[test]
+    fn test_intern_bytes_in() {
+        let repo = Repository::new();
+        let hello = ScopedSto::intern_in("hello", &repo);
+        let bytes = (0..2000u32)
+            .map(|i| {
+                let mut bytes = i.to_le_bytes().to_vec();
+                bytes.push(0xFF);
+                bytes
+            })
+            .collect::<Vec<_>>();
+        let stos = bytes
+            .iter()
+            .map(|b| repo.intern_bytes_in(b))
+            .collect::<Vec<_>>();
+        for (b, sto) in bytes.iter().zip(&stos) {
+            assert_eq!(sto.as_bytes(), b.as_slice());
+            assert_eq!(*sto, repo.intern_bytes_in(b));
+        }
+        assert_eq!(repo.intern_bytes_in(b"hello").as_bytes(), b"hello");
+        assert_eq!(repo.len(), 1);
+        assert!(repo.iter().all(|s| s == hello));
+        assert_eq!(format!("{:?}", stos[0]), r#""\x00\x00\x00\x00\xff""#);
+    }
+
+    #[test]
+    fn test_hash_contents() {
+        use crate::PrehashedSto;
+        use std::collections::hash_map::RandomState;
+        use std::collections::{HashMap, HashSet};
+        use std::hash::{BuildHasher, Hash, Hasher};
+
+        // `BuildHasher::hash_one` is newer than the MSRV
+        #[allow(clippy::manual_hash_one)]
+        fn hash_with<T: Hash + ?Sized>(state: &RandomState, value: &T) -> u64 {
+            let mut hasher = state.build_hasher();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let repo = Repository::new();
+        let state = RandomState::new();
+        let mut map = HashMap::new();
+        for i in 0..1000 {
+            let sto = ScopedSto::intern_in(i.to_string(), &repo);
+            assert_eq!(hash_with(&state, &sto), hash_with(&state, sto.as_str()));
+            map.insert(sto, i);
+        }
+        for i in 0..1000 {
+            assert_eq!(map.get(i.to_string().as_str()), Some(&i));
+            assert_eq!(map[&ScopedSto::intern_in(i.to_string(), &repo)], i);
+        }
+        assert!(!map.contains_key("1000"));
+
+        let prehashed = (0..1000)
+            .map(|i| PrehashedSto(ScopedSto::intern_in(i.to_string(), &repo)))
+            .collect::<HashSet<_>>();
+        assert_eq!(prehashed.len(), 1000);
+        let hello = PrehashedSto::from(ScopedSto::intern_in("999", &repo));
+        assert!(prehashed.contains(&hello));
+        assert_eq!(hello.into_inner().as_str(), "999");
+        assert_eq!(hello.len(), 3);
+    }
+
+    #[test]
+    fn test_sto_build_hasher() {
+        use crate::hashers::{StoBuildHasher, StoHasher};
+        use crate::PrehashedSto;
+        use std::collections::{HashMap, HashSet};
+        use std::hash::{Hash, Hasher};
+
+        let repo = Repository::new();
+        let mut map = HashMap::with_hasher(StoBuildHasher);
+        for i in 0..1000 {
+            let sto = PrehashedSto(ScopedSto::intern_in(i.to_string(), &repo));
+            let mut hasher = StoHasher::default();
+            sto.hash(&mut hasher);
+            assert_eq!(hasher.finish(), sto.0.hash());
+            map.insert(sto, i);
+        }
+        for i in 0..1000 {
+            let sto = PrehashedSto(ScopedSto::intern_in(i.to_string(), &repo));
+            assert_eq!(map[&sto], i);
+        }
+
+        // other keys still work
+        let strings = (0..1000)
+            .map(|i| "x".repeat(i))
+            .collect::<HashSet<_, StoBuildHasher>>();
+        assert_eq!(strings.len(), 1000);
+        assert!(strings.contains(&"x".repeat(999)));
+        assert!(!strings.contains("y"));
+    }
+
+    #[test]
+    fn test_by_content() {
+        use crate::ByContent;
+        use std::collections::HashMap;
+
+        let repo = Repository::new();
+        let other = Repository::new();
+        let map = (0..1000)
+            .map(|i| (ByContent(ScopedSto::intern_in(i.to_string(), &repo)), i))
+            .collect::<HashMap<_, _>>();
+        for i in 0..1000 {
+            assert_eq!(map.get(i.to_string().as_str()), Some(&i));
+            let key = ByContent(ScopedSto::intern_in(i.to_string(), &other));
+            assert_eq!(map.get(&key), Some(&i));
+        }
+        assert_eq!(map.get("hello"), None);
+    }
+
+    #[test]
+    fn test_with_hasher() {
+        use std::collections::hash_map::RandomState;
+
+        let repo = Repository::with_hasher(RandomState::new());
+        let stos = (0..10_000)
+            .map(|i| ScopedSto::intern_in(i.to_string(), &repo))
+            .collect::<Vec<_>>();
+        for (i, &sto) in stos.iter().enumerate() {
+            assert_eq!(ScopedSto::intern_in(i.to_string(), &repo), sto);
+            assert_eq!(repo.get(&i.to_string()), Some(sto));
+        }
+        assert_eq!(repo.len(), stos.len());
+
+        let copy = repo.clone_contents();
+        assert_eq!(copy.len(), stos.len());
+        assert_eq!(Repository::new().intern_tiered("42", &copy), "42");
+    }
+
+    #[test]
+    fn test_with_seed() {
+        let a = Repository::with_seed([1, 2, 3, 4]);
+        let b = Repository::with_seed([1, 2, 3, 4]);
+        let c = Repository::with_seed([4, 3, 2, 1]);
+        let hashes = |repo: &Repository| {
+            (0..1000)
+                .map(|i| ScopedSto::intern_in(i.to_string(), repo).hash())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(hashes(&a), hashes(&b));
+        assert_ne!(hashes(&a), hashes(&c));
+        assert_eq!(a.hash_of("42"), hashes(&b)[42]);
+        assert_ne!(a.hash_of("42"), c.hash_of("42"));
+        let strings = |repo: &Repository| repo.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(strings(&a), strings(&b));
+    }
+
+    #[test]
+    fn test_with_buckets() {
+        fn check<const BUCKETS: usize>() {
+            let mut repo = Repository::<BUCKETS>::with_buckets();
+            let stos = (0..5000)
+                .map(|i| ScopedSto::intern_in(i.to_string(), &repo))
+                .collect::<Vec<_>>();
+            for (i, &sto) in stos.iter().enumerate() {
+                assert_eq!(ScopedSto::intern_in(i.to_string(), &repo), sto);
+            }
+            assert_eq!(repo.len(), stos.len());
+            assert_eq!(repo.iter().count(), stos.len());
+            repo.shrink_bucket(BUCKETS - 1);
+        }
+        check::<1>();
+        check::<2>();
+        check::<4>();
+        check::<256>();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_with_buckets_not_power_of_two() {
+        Repository::<3>::with_buckets();
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut repo = Repository::builder().indexed().build();
+        for round in 0..3 {
+            let large = "x".repeat(CHUNK_DEFAULT_CAPACITY + round);
+            let stos = (0..5000)
+                .map(|i| ScopedSto::intern_in(i.to_string(), &repo))
+                .collect::<Vec<_>>();
+            ScopedSto::intern_in(&large, &repo);
+            repo.intern_bytes_in(b"\xFF");
+            assert_eq!(stos[42], "42");
+            assert_eq!(repo.resolve(0).unwrap(), "0");
+            assert_eq!(repo.len(), 5001);
+
+            repo.clear();
+            assert!(repo.is_empty());
+            assert_eq!(repo.allocated_memory(), 0);
+            assert!(repo.resolve(0).is_none());
+        }
+
+        let buf = Box::leak(vec![0; BUCKET_NUMBER * 1024].into_boxed_slice());
+        let range = buf.as_ptr_range();
+        let mut repo = Repository::with_initial_buffer(buf);
+        for _ in 0..3 {
+            let stos = (0..5000)
+                .map(|i| ScopedSto::intern_in(i.to_string(), &repo))
+                .collect::<Vec<_>>();
+            assert!(stos.iter().any(|s| range.contains(&s.as_str().as_ptr())));
+            repo.clear();
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_intern_reversed_domain() {
+        let repo = Repository::new();
+        for (input, expected) in [
+            ("www.example.com", "com.example.www"),
+            ("www.example.com.", "com.example.www"),
+            ("localhost", "localhost"),
+            ("a..b", "b..a"),
+            (".a", "a."),
+            ("", ""),
+            (".", ""),
+            ("..", "."),
+            ("10.0.0.1", "10.0.0.1"),
+            ("::1", "::1"),
+            ("10.0.0.256", "256.0.0.10"),
+        ] {
+            let sto = repo.intern_reversed_domain(input);
+            assert_eq!(sto.as_str(), expected);
+            assert_eq!(sto, ScopedSto::intern_in(expected, &repo));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "arbitrary")]
+    fn test_arbitrary() {
+        use crate::Sto;
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let empty = Sto::arbitrary(&mut Unstructured::new(&[])).unwrap();
+        assert_eq!(empty, Sto::from(""));
+
+        let long = "x".repeat(1 << 20);
+        let sto = Sto::arbitrary_take_rest(Unstructured::new(long.as_bytes())).unwrap();
+        assert_eq!(sto, Sto::from(long));
+
+        let data = (0..4096u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8)
+            .collect::<Vec<_>>();
+        let mut u = Unstructured::new(&data);
+        while !u.is_empty() {
+            let sto = Sto::arbitrary(&mut u).unwrap();
+            assert_eq!(sto, Sto::from(sto.as_str()));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "thread_local")]
+    fn test_local_repository() {
+        use crate::{local_repository, LocalSto};
+
+        let repo = Repository::new();
+        let locals = std::thread::scope(|s| {
+            let handles = (0..4)
+                .map(|t| {
+                    let repo = &repo;
+                    s.spawn(move || {
+                        (0..1000).for_each(|i| {
+                            LocalSto::from((t * 500 + i).to_string());
+                        });
+                        assert_eq!(LocalSto::from("0"), LocalSto::from("0"));
+                        assert_eq!(local_repository().len(), 1000 + t.min(1));
+                        repo.merge(local_repository());
+                        local_repository() as *const Repository as usize
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(repo.len(), 2500);
+        assert!((1..4).all(|i| locals[..i].iter().all(|&p| p != locals[i])));
+    }
+
+    #[test]
+    #[cfg(feature = "global")]
+    #[allow(unreachable_pub)]
+    fn test_define_global_repository() {
+        crate::define_global_repository!(types);
+        crate::define_global_repository!(pub(crate) fields);
+
+        let a = types::Sto::from("name");
+        let b = fields::Sto::from("name");
+        assert_eq!(a, types::Sto::from(String::from("name")));
+        assert_eq!(a.as_str(), b.as_str());
+        assert!(!a.as_sto().ptr_eq(&b.as_sto()));
+        assert_eq!(&*a, "name");
+        assert_eq!(format!("{a} {b:?}"), "name name");
+
+        assert_eq!(types::repository().len(), 1);
+        assert!(!std::ptr::eq(types::repository(), fields::repository()));
+        assert!(!std::ptr::eq(types::repository(), crate::repository()));
+    }
+
+    #[test]
+    #[cfg(feature = "unicode")]
+    fn test_intern_normalized() {
+        let repo = Repository::new();
+        let nfc = repo.intern_normalized("Caf\u{e9} \u{212b}");
+        assert_eq!(nfc.as_str(), "Caf\u{e9} \u{c5}");
+        assert_eq!(repo.intern_normalized("Cafe\u{301} A\u{30a}"), nfc);
+        assert_eq!(repo.intern_normalized("Caf\u{e9} \u{c5}"), nfc);
+        assert_eq!(ScopedSto::intern_in("Caf\u{e9} \u{c5}", &repo), nfc);
+        assert_eq!(repo.intern_normalized("hello"), "hello");
+        assert_eq!(repo.intern_normalized(""), "");
+        assert_eq!(repo.len(), 3);
+    }
+
+    #[test]
+    #[cfg(feature = "refcount")]
+    fn test_release() {
+        let repo = Repository::<1>::with_buckets();
+        let a = ScopedSto::intern_in("hello", &repo);
+        let b = ScopedSto::intern_in("hello", &repo);
+        assert!(!unsafe { a.release(&repo) });
+        assert_eq!(repo.get("hello"), Some(b));
+        assert!(unsafe { b.release(&repo) });
+        assert_eq!(repo.get("hello"), None);
+        assert_eq!(repo.iter().count(), 0);
+
+        // revived before it is removed
+        let c = ScopedSto::intern_in("hello", &repo);
+        assert!(c.ptr_eq(&a));
+        assert_eq!(repo.iter().collect::<Vec<_>>(), [c]);
+        assert!(unsafe { c.release(&repo) });
+
+        // fill the table, release all strings, and intern as many new ones
+        let count = ENTRIES_INITIAL_CAPACITY / 4 * 3 - 1;
+        let stos = (0..count)
+            .map(|i| ScopedSto::intern_in(format!("{i:08}"), &repo))
+            .collect::<Vec<_>>();
+        let stats = repo.memory_stats();
+        assert!(stos.iter().all(|sto| unsafe { sto.release(&repo) }));
+        (0..count + 1).for_each(|i| {
+            ScopedSto::intern_in(format!("{:08}", i + count), &repo);
+        });
+        assert_eq!(repo.len(), count + 1);
+        assert_eq!(repo.memory_stats().arena_bytes, stats.arena_bytes);
+        assert_eq!(repo.memory_stats().entries_bytes, stats.entries_bytes);
+        assert!(repo.get("00000000").is_none());
+        assert_eq!(
+            repo.get(&format!("{count:08}")).unwrap(),
+            format!("{count:08}")
+        );
+
+        let repo = Repository::builder().indexed().build();
+        let sto = ScopedSto::intern_in("hello", &repo);
+        assert!(!unsafe { sto.release(&repo) });
+    }
+
+    #[test]
+    #[cfg(feature = "refcount")]
+    fn test_release_after_bulk_copies() {
+        let repo = Repository::<1>::with_buckets();
+        let hello = ScopedSto::intern_in("hello", &repo);
+        let other = Repository::<1>::with_buckets();
+        ScopedSto::intern_in("hello", &other);
+        ScopedSto::intern_in("world", &other);
+
+        // bulk copies do not add references to interned strings
+        assert_eq!(repo.merge(&other), 1);
+        (&repo).extend(["hello", "world"]);
+        let copy = repo.clone_contents();
+        assert_eq!(copy.len(), 2);
+        assert!(unsafe { hello.release(&repo) });
+        assert!(repo.get("hello").is_none());
+        assert_eq!(repo.to_sorted_vec(), ["world"]);
+
+        // a released string is revived by a bulk copy
+        assert_eq!(repo.merge(&other), 0);
+        assert!(repo.get("hello").is_some());
+        let world = ScopedSto::intern_in("world", &repo);
+        assert!(!unsafe { world.release(&repo) });
+        assert!(repo.get("world").is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "refcount")]
+    #[should_panic]
+    fn test_release_too_many() {
+        let repo = Repository::new();
+        let sto = ScopedSto::intern_in("hello", &repo);
+        unsafe {
+            sto.release(&repo);
+            sto.release(&repo);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_repository_seed() {
+        use crate::RepositorySeed;
+        use serde::de::{DeserializeSeed, Deserializer, SeqAccess, Visitor};
+        use std::fmt;
+
+        struct VecSeed<'a>(&'a Repository);
+
+        impl<'de, 'a> DeserializeSeed<'de> for VecSeed<'a> {
+            type Value = Vec<ScopedSto<'a>>;
+
+            fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                deserializer.deserialize_seq(self)
+            }
+        }
+
+        impl<'de, 'a> Visitor<'de> for VecSeed<'a> {
+            type Value = Vec<ScopedSto<'a>>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a sequence of strings")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut stos = Vec::new();
+                while let Some(sto) = seq.next_element_seed(RepositorySeed(self.0))? {
+                    stos.push(sto);
+                }
+                Ok(stos)
+            }
+        }
+
+        let repo = Repository::new();
+        // the escaped string can not be borrowed from the input
+        let json = r#"["hello","w\u006frld","hello"]"#;
+        let stos = VecSeed(&repo)
+            .deserialize(&mut serde_json::Deserializer::from_str(json))
+            .unwrap();
+        assert_eq!(stos, ["hello", "world", "hello"]);
+        assert_eq!(stos[0], stos[2]);
+        assert_eq!(repo.len(), 2);
+
+        let json = serde_json::to_string(&stos).unwrap();
+        assert_eq!(json, r#"["hello","world","hello"]"#);
+        let from_reader = VecSeed(&repo)
+            .deserialize(&mut serde_json::Deserializer::from_reader(json.as_bytes()))
+            .unwrap();
+        assert_eq!(from_reader, stos);
+        assert!(RepositorySeed(&repo)
+            .deserialize(&mut serde_json::Deserializer::from_str("42"))
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "debug-timing")]
+    fn test_timing_report() {
+        let repo = Repository::new();
+        ScopedSto::intern_in("hello", &repo);
+        ScopedSto::intern_in("hello", &repo);
+        ScopedSto::intern_in("world", &repo);
+
+        let report = repo.timing_report();
+        assert_eq!(report.calls, 3);
+        assert!(report.hash_nanos > 0);
+        assert!(report.alloc_nanos > 0);
+    }
+
+    #[test]
+    #[cfg(feature = "global")]
+    fn test_sto() {
+        use crate::Sto;
+
+        let a = Sto::from("hello world");
+        let b = Sto::from("hello world");
+        assert_eq!(a, b);
+        assert_eq!(a.as_str(), b.as_str());
+        assert_eq!(a.hash(), b.hash());
+
+        let a = Sto::from("hello");
+        let b = Sto::from("world");
+        assert_ne!(a, b);
+        assert_ne!(a.as_str(), b.as_str());
+        assert_ne!(a.hash(), b.hash());
+    }
+
+    #[test]
+    #[cfg(feature = "global")]
+    fn test_sto_tiny() {
+        use crate::{repository, Sto};
+
+        for c in (0..128u8).map(char::from) {
+            let a = Sto::from(c.to_string());
+            let b = Sto::from(c.to_string());
+            assert_eq!(a, b);
+            assert_eq!(a, c.to_string());
+            assert_eq!(a, repository().intern_char(c));
+        }
+
+        let empty = Sto::from("");
+        assert_eq!(empty, "");
+        assert_eq!(empty, Sto::from(""));
+        assert_eq!(empty, Sto::intern_in("", repository()));
     }
 }
@@ -0,0 +1,270 @@
+use crate::builder::Config;
+use crate::entry::Entry;
+use crate::repository::{default_hash_builder, hash_with, BucketImpl};
+use crate::{Repository, ScopedSto};
+use ahash::RandomState;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::marker::PhantomData;
+use core::ops::Deref;
+
+/// A single-threaded store of interned strings, without any lock.
+///
+/// Unlike [Repository], a `LocalRepository` can not be shared between threads,
+/// but interning into it never contends with other threads.
+/// Strings interned in a `LocalRepository` can be moved into a `Repository` later,
+/// see [Repository::promote_local].
+///
+/// ## Example
+/// ```
+/// # use sto::LocalRepository;
+/// let local = LocalRepository::new();
+/// let a = local.intern("hello");
+/// let b = local.intern("hello");
+/// assert_eq!(a, b);
+/// ```
+pub struct LocalRepository {
+    bucket: RefCell<BucketImpl>,
+    config: Config,
+    hasher: RandomState,
+}
+
+impl LocalRepository {
+    /// Constructs a new [LocalRepository].
+    ///
+    /// Like [Repository::new], it does not allocate memory initially.
+    pub fn new() -> Self {
+        Self {
+            bucket: RefCell::default(),
+            config: Config::default(),
+            hasher: default_hash_builder(),
+        }
+    }
+
+    /// Interns a string.
+    pub fn intern<S>(&self, string: S) -> LocalSto<'_>
+    where
+        S: AsRef<str>,
+    {
+        let string = string.as_ref();
+        let hash = hash_with(&self.hasher, string.as_bytes());
+        let outcome = self
+            .bucket
+            .borrow_mut()
+            .get_or_insert(hash, string.as_bytes(), &self.config);
+        LocalSto::new(outcome.entry)
+    }
+
+    /// Returns the interned string if it exists, without inserting it.
+    pub fn get(&self, string: &str) -> Option<LocalSto<'_>> {
+        let hash = hash_with(&self.hasher, string.as_bytes());
+        self.bucket
+            .borrow()
+            .entries
+            .get(hash, string.as_bytes())
+            .map(LocalSto::new)
+    }
+
+    /// Returns the number of bytes allocated by the [LocalRepository].
+    pub fn allocated_memory(&self) -> usize {
+        let bucket = self.bucket.borrow();
+        bucket.entries.allocated_memory() + bucket.arena.allocated_memory()
+    }
+
+    pub(crate) fn entries(&self) -> Vec<Entry> {
+        self.bucket.borrow().entries.iter().collect()
+    }
+}
+
+impl Default for LocalRepository {
+    /// See [LocalRepository::new].
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for LocalRepository {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalRepository").finish()
+    }
+}
+
+/// Represents a string interned in a [LocalRepository].
+///
+/// It works like [ScopedSto], but can not be sent to other threads,
+/// as its `LocalRepository` can not be shared between threads.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct LocalSto<'a> {
+    entry: Entry,
+    _phantom: PhantomData<(&'a (), *const ())>,
+}
+
+impl<'a> LocalSto<'a> {
+    fn new(entry: Entry) -> Self {
+        Self {
+            entry,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// The interned string.
+    pub fn as_str(&self) -> &'a str {
+        self.entry.as_str()
+    }
+
+    /// The precomputed hash.
+    pub fn hash(&self) -> u64 {
+        self.entry.hash()
+    }
+
+    /// The length of the interned string.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.as_str().len()
+    }
+}
+
+#[cfg(feature = "thread_local")]
+impl LocalSto<'static> {
+    /// A shortcut to intern a string in the [Repository] of the current thread,
+    /// see [local_repository].
+    ///
+    /// The returned `LocalSto` can not be sent to other threads, so it is never compared
+    /// with handles of the `Repository` of another thread.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::LocalSto;
+    /// let a = LocalSto::from("hello");
+    /// assert_eq!(a, LocalSto::from("hello"));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "thread_local")))]
+    pub fn from<S>(string: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        Self::new(local_repository().get_or_insert(string.as_ref()))
+    }
+}
+
+impl Hash for LocalSto<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash().hash(state)
+    }
+}
+
+impl PartialOrd<Self> for LocalSto<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for LocalSto<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl PartialEq<&str> for LocalSto<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl<'a> AsRef<str> for LocalSto<'a> {
+    fn as_ref(&self) -> &'a str {
+        self.as_str()
+    }
+}
+
+impl<'a> Deref for LocalSto<'a> {
+    type Target = str;
+
+    fn deref(&self) -> &'a Self::Target {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for LocalSto<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for LocalSto<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Returns the [Repository] of the current thread, where [LocalSto::from] interns strings.
+///
+/// Every thread interns into its own `Repository` without contending with other threads.
+/// Strings interned by worker threads can be consolidated by [Repository::merge].
+///
+/// The `Repository` of a thread is never freed, even after the thread exits,
+/// so it suits long-lived worker threads rather than many short-lived ones.
+///
+/// ## Example
+/// ```
+/// # use sto::{local_repository, LocalSto, Repository};
+/// let repository = Repository::new();
+/// std::thread::scope(|s| {
+///     for i in 0..4 {
+///         let repository = &repository;
+///         s.spawn(move || {
+///             LocalSto::from(i.to_string());
+///             repository.merge(local_repository());
+///         });
+///     }
+/// });
+/// assert_eq!(repository.len(), 4);
+/// ```
+#[cfg(feature = "thread_local")]
+#[cfg_attr(docsrs, doc(cfg(feature = "thread_local")))]
+pub fn local_repository() -> &'static Repository {
+    std::thread_local! {
+        static REPOSITORY: &'static Repository = Box::leak(Box::default());
+    }
+    REPOSITORY.with(|repository| *repository)
+}
+
+impl<const BUCKETS: usize, H: BuildHasher> Repository<BUCKETS, H> {
+    /// Interns all strings of a [LocalRepository] into this [Repository],
+    /// and returns the handles of both sides for each string.
+    ///
+    /// This is typically used to merge strings interned by worker threads without contention
+    /// into a shared `Repository` at a synchronization point.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{LocalRepository, Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let local = LocalRepository::new();
+    /// local.intern("hello");
+    ///
+    /// let promoted = repository.promote_local(&local);
+    /// assert_eq!(promoted.len(), 1);
+    /// let (local_sto, sto) = promoted[0];
+    /// assert_eq!(local_sto.as_str(), sto.as_str());
+    /// assert_eq!(sto, ScopedSto::intern_in("hello", &repository));
+    /// ```
+    pub fn promote_local<'l>(
+        &self,
+        local: &'l LocalRepository,
+    ) -> Vec<(LocalSto<'l>, ScopedSto<'_>)> {
+        local
+            .entries()
+            .into_iter()
+            .map(|entry| {
+                (
+                    LocalSto::new(entry),
+                    ScopedSto::new(self.get_or_insert(entry.as_str())),
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,94 @@
+/// Defines a module holding a global [Repository](crate::Repository) of its own,
+/// with a handle type `Sto` which is only interned in it.
+///
+/// Strings of separate domains, e.g. type names and field names, can be interned in separate
+/// global repositories, so their handles can not be compared with each other by mistake.
+///
+/// The module provides:
+/// - `repository()`, which returns its `Repository`, initialized on first use,
+/// - `Sto`, a wrapper of [Sto](crate::Sto) interned in that `Repository`,
+/// - `Sto::from`, which interns a string in that `Repository`.
+///
+/// ## Example
+/// ```
+/// sto::define_global_repository!(pub types);
+/// sto::define_global_repository!(fields);
+///
+/// let a = types::Sto::from("name");
+/// assert_eq!(a, types::Sto::from("name"));
+/// assert_eq!(a.as_str(), fields::Sto::from("name").as_str());
+/// assert_eq!(types::repository().len(), 1);
+/// ```
+///
+/// Handles of different domains have different types:
+/// ```compile_fail
+/// sto::define_global_repository!(types);
+/// sto::define_global_repository!(fields);
+///
+/// assert_ne!(types::Sto::from("name"), fields::Sto::from("name"));
+/// ```
+#[macro_export]
+#[cfg_attr(docsrs, doc(cfg(feature = "global")))]
+macro_rules! define_global_repository {
+    ($vis:vis $name:ident) => {
+        /// A global `Repository` of its own, defined by `define_global_repository!`.
+        $vis mod $name {
+            /// Returns the `Repository` of this module.
+            pub fn repository() -> &'static $crate::Repository {
+                static REPOSITORY: $crate::__private::OnceCell<$crate::Repository> =
+                    $crate::__private::OnceCell::new();
+                REPOSITORY.get_or_init($crate::Repository::new)
+            }
+
+            /// A string interned in the `Repository` of this module.
+            #[derive(Copy, Clone, Eq, PartialEq, Hash, PartialOrd, Ord)]
+            pub struct Sto($crate::Sto);
+
+            impl Sto {
+                /// Interns a string in the `Repository` of this module.
+                pub fn from<S>(string: S) -> Self
+                where
+                    S: AsRef<str>,
+                {
+                    Self($crate::Sto::intern_in(string, repository()))
+                }
+
+                /// The interned string.
+                pub fn as_str(&self) -> &'static str {
+                    self.0.as_str()
+                }
+
+                /// The handle in the `Repository` of this module.
+                pub fn as_sto(&self) -> $crate::Sto {
+                    self.0
+                }
+            }
+
+            impl ::core::ops::Deref for Sto {
+                type Target = str;
+
+                fn deref(&self) -> &'static str {
+                    self.as_str()
+                }
+            }
+
+            impl ::core::convert::AsRef<str> for Sto {
+                fn as_ref(&self) -> &str {
+                    self.as_str()
+                }
+            }
+
+            impl ::core::fmt::Display for Sto {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    ::core::fmt::Display::fmt(&self.0, f)
+                }
+            }
+
+            impl ::core::fmt::Debug for Sto {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    ::core::fmt::Debug::fmt(&self.0, f)
+                }
+            }
+        }
+    };
+}
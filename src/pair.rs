@@ -0,0 +1,128 @@
+use crate::entry::Entry;
+use crate::{Repository, ScopedSto};
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::marker::PhantomData;
+use core::mem::size_of;
+use core::ptr::NonNull;
+
+/// The length of the key of a pair, which holds the entries of both components.
+pub(crate) const PAIR_KEY_LEN: usize = 2 * size_of::<usize>();
+
+/// Represents an interned pair of strings, like a qualified `(namespace, name)`.
+///
+/// Both components are interned, and the pair is interned too, keyed on their entries,
+/// so identical pairs share a handle.
+///
+/// ## Size
+/// It has the same size as a pointer.
+/// ```
+/// # use sto::ScopedPair;
+/// assert_eq!(std::mem::size_of::<ScopedPair>(), std::mem::size_of::<usize>());
+/// ```
+///
+/// ## Compare
+/// Two `ScopedPair`s from the same [Repository] are compared cheaply by pointers,
+/// just like [ScopedSto]. They are ordered by their first components, then their second ones.
+/// ```
+/// # use sto::Repository;
+/// let repository = Repository::new();
+/// let a = repository.intern_pair("std", "vec");
+/// let b = repository.intern_pair("std", "vec");
+/// assert_eq!(a, b);
+/// assert_ne!(a, repository.intern_pair("vec", "std"));
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct ScopedPair<'a> {
+    /// the interned key holding the entries of both components
+    entry: Entry,
+    _phantom: PhantomData<&'a ()>,
+}
+
+impl<'a> ScopedPair<'a> {
+    /// The first interned component.
+    pub fn first(&self) -> ScopedSto<'a> {
+        ScopedSto::new(pair_entries(self.entry.as_bytes()).0)
+    }
+
+    /// The second interned component.
+    pub fn second(&self) -> ScopedSto<'a> {
+        ScopedSto::new(pair_entries(self.entry.as_bytes()).1)
+    }
+}
+
+unsafe impl Send for ScopedPair<'_> {}
+
+unsafe impl Sync for ScopedPair<'_> {}
+
+impl Hash for ScopedPair<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.first(), self.second()).hash(state)
+    }
+}
+
+impl Ord for ScopedPair<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.first(), self.second()).cmp(&(other.first(), other.second()))
+    }
+}
+
+impl PartialOrd for ScopedPair<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Debug for ScopedPair<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("")
+            .field(&self.first())
+            .field(&self.second())
+            .finish()
+    }
+}
+
+impl<const BUCKETS: usize, H: BuildHasher> Repository<BUCKETS, H> {
+    /// Interns both strings of a pair, then the pair itself.
+    ///
+    /// Pairs are kept in a single bucket apart from strings, so interning pairs
+    /// from many threads contends on one lock. They are not counted by [Repository::len].
+    ///
+    /// See [ScopedPair].
+    pub fn intern_pair<A, B>(&self, first: A, second: B) -> ScopedPair<'_>
+    where
+        A: AsRef<str>,
+        B: AsRef<str>,
+    {
+        let first = ScopedSto::intern_in(first, self);
+        let second = ScopedSto::intern_in(second, self);
+        let key = pair_key(first.entry, second.entry);
+        let hash = self.hash_bytes(&key);
+        let outcome = self.pairs.0.lock().get_or_insert(hash, &key, &self.config);
+        ScopedPair {
+            entry: outcome.entry,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// The key of a pair, made of the addresses of the entries of its components.
+pub(crate) fn pair_key(first: Entry, second: Entry) -> [u8; PAIR_KEY_LEN] {
+    let mut key = [0; PAIR_KEY_LEN];
+    let (a, b) = key.split_at_mut(size_of::<usize>());
+    a.copy_from_slice(&(first.0.as_ptr() as usize).to_ne_bytes());
+    b.copy_from_slice(&(second.0.as_ptr() as usize).to_ne_bytes());
+    key
+}
+
+/// The entries of the components of a pair, read from its key.
+pub(crate) fn pair_entries(key: &[u8]) -> (Entry, Entry) {
+    let entry = |bytes: &[u8]| {
+        let mut addr = [0; size_of::<usize>()];
+        addr.copy_from_slice(bytes);
+        Entry(unsafe { NonNull::new_unchecked(usize::from_ne_bytes(addr) as *mut u8) })
+    };
+    let (a, b) = key.split_at(size_of::<usize>());
+    (entry(a), entry(b))
+}
@@ -0,0 +1,57 @@
+use crate::ScopedSto;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+
+/// A wrapper of [ScopedSto] which hashes the precomputed hash rather than the string,
+/// so hashing it never reads the string.
+///
+/// Unlike `ScopedSto`, it hashes differently from `str`, so a `HashMap` keyed by
+/// `PrehashedSto` can only be looked up with handles. Handles of the same
+/// [Repository](crate::Repository) are compared by pointers, just like `ScopedSto`.
+///
+/// ## Example
+/// ```
+/// # use std::collections::HashMap;
+/// # use sto::{PrehashedSto, Repository, ScopedSto};
+/// let repository = Repository::new();
+/// let mut map = HashMap::new();
+/// map.insert(PrehashedSto(ScopedSto::intern_in("key", &repository)), 42);
+/// let key = PrehashedSto(ScopedSto::intern_in("key", &repository));
+/// assert_eq!(map.get(&key), Some(&42));
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct PrehashedSto<'a>(pub ScopedSto<'a>);
+
+impl<'a> PrehashedSto<'a> {
+    /// Returns the wrapped [ScopedSto].
+    pub fn into_inner(self) -> ScopedSto<'a> {
+        self.0
+    }
+}
+
+impl<'a> From<ScopedSto<'a>> for PrehashedSto<'a> {
+    fn from(value: ScopedSto<'a>) -> Self {
+        Self(value)
+    }
+}
+
+impl Hash for PrehashedSto<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.0.hash())
+    }
+}
+
+impl<'a> Deref for PrehashedSto<'a> {
+    type Target = ScopedSto<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl fmt::Debug for PrehashedSto<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
@@ -1,11 +1,33 @@
-use crate::arena::Arena;
-use crate::constants::{BUCKET_NUMBER, BUCKET_RSHIFT};
-use crate::entry::{Entries, Entry};
+use crate::arena::{Arena, ChunkId};
+use crate::builder::{Config, RepositoryBuilder};
+use crate::constants::{ALLOC_ALIGNMENT, BUCKET_NUMBER};
+use crate::entry::{Entries, Entry, Outcome};
+use crate::error::{DuplicateError, InternError};
+use crate::pair::{pair_entries, pair_key};
+use crate::scratch::with_scratch;
+use crate::stats::{BucketStat, MemoryStats};
+use crate::sync::{BucketLock, Mutex, ReadGuard};
+#[cfg(feature = "debug-timing")]
+use crate::timing::TimingReport;
+use crate::timing::{Stopwatch, Timing};
+use crate::ScopedSto;
 use ahash::RandomState;
-use parking_lot::Mutex;
-use std::fmt;
-use std::fmt::Formatter;
-use std::hash::{BuildHasher, Hasher};
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Formatter;
+use core::hash::{BuildHasher, Hasher};
+use core::iter::FusedIterator;
+use core::mem::size_of;
+use core::ops::Range;
+use core::ptr::{copy_nonoverlapping, NonNull};
+#[cfg(feature = "refcount")]
+use core::sync::atomic::Ordering as AtomicOrdering;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::net::IpAddr;
 
 /// A [Repository] used to store interned strings.
 ///
@@ -16,76 +38,2013 @@ use std::hash::{BuildHasher, Hasher};
 /// The `Repository` can be safely shared among multiple threads.
 ///
 /// To intern a string, see [ScopedSto::intern_in](crate::ScopedSto::intern_in).
-pub struct Repository {
-    buckets: [Bucket; BUCKET_NUMBER],
+///
+/// ## Hasher
+/// By default, strings are hashed by `ahash` with fixed seeds.
+/// Another [BuildHasher] can be used with [Repository::with_hasher],
+/// e.g. `std::collections::hash_map::RandomState` to resist collision attacks from untrusted input.
+///
+/// ## Buckets
+/// Strings are sharded by hash into `BUCKETS` buckets, each with its own lock.
+/// The default is 64, see [Repository::with_buckets] to choose another power of two.
+pub struct Repository<const BUCKETS: usize = 64, H = RandomState> {
+    buckets: [Bucket; BUCKETS],
+    pub(crate) config: Config,
+    timing: Timing,
+    /// the interned entries in the order of their ids, only filled if `config.indexed`
+    ids: Mutex<Vec<Entry>>,
+    /// byte strings, which are kept apart as they may not be UTF-8
+    pub(crate) bytes: Bucket,
+    /// interned pairs, keyed on the entries of their components
+    pub(crate) pairs: Bucket,
+    hasher: H,
+}
+
+impl Repository {
+    /// Constructs a new [Repository].
+    ///
+    /// The newly constructed `Repository` does not allocate memory initially.
+    /// Memory allocation only occurs when there is a string to be interned.
+    ///
+    ///
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::Repository;
+    /// let repository = Repository::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::with_config(Config::default())
+    }
+
+    /// Returns a [RepositoryBuilder] to configure a new [Repository].
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::Repository;
+    /// let repository = Repository::builder().max_probe_length(16).build();
+    /// ```
+    pub fn builder() -> RepositoryBuilder {
+        RepositoryBuilder::new()
+    }
+
+    /// Constructs a new [Repository] whose interned strings are aligned to `alignment`.
+    ///
+    /// See [RepositoryBuilder::string_alignment].
+    pub fn with_string_alignment(alignment: usize) -> Self {
+        Self::builder().string_alignment(alignment).build()
+    }
+
+    /// Constructs a new [Repository] which writes `sentinel` after every interned string.
+    ///
+    /// See [RepositoryBuilder::sentinel].
+    pub fn with_sentinel(sentinel: u8) -> Self {
+        Self::builder().sentinel(sentinel).build()
+    }
+
+    /// Constructs a new [Repository] whose arena chunks have `capacity` bytes.
+    ///
+    /// See [RepositoryBuilder::chunk_capacity].
+    pub fn with_chunk_capacity(capacity: usize) -> Self {
+        Self::builder().chunk_capacity(capacity).build()
+    }
+
+    /// Constructs a new [Repository] which can hold about `capacity` strings
+    /// without growing its hash tables.
+    ///
+    /// The capacity is divided evenly among buckets, so a bucket which receives more strings
+    /// than others may still grow.
+    ///
+    /// ## Panics
+    /// Panics if the hash tables can not be allocated.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::Repository;
+    /// let repository = Repository::with_capacity(100_000);
+    /// assert!(repository.memory_stats().entries_bytes > 0);
+    /// assert!((0..100_000).all(|i| !repository.intern_grow_aware(i.to_string()).1));
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut repository = Self::new();
+        repository.reserve(capacity);
+        repository
+    }
+
+    /// Constructs a new [Repository] whose hash tables grow at the load factor `num / den`.
+    ///
+    /// See [RepositoryBuilder::load_factor].
+    pub fn with_load_factor(num: u8, den: u8) -> Self {
+        Self::builder().load_factor(num, den).build()
+    }
+
+    /// Constructs a new [Repository] which hashes strings by `ahash` with the given seeds,
+    /// instead of the fixed seeds of [Repository::new].
+    ///
+    /// Repositories with the same seeds compute the same hashes, so strings are distributed
+    /// among buckets identically.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let a = Repository::with_seed([1, 2, 3, 4]);
+    /// let b = Repository::with_seed([1, 2, 3, 4]);
+    /// assert_eq!(
+    ///     ScopedSto::intern_in("hello", &a).hash(),
+    ///     ScopedSto::intern_in("hello", &b).hash(),
+    /// );
+    /// ```
+    pub fn with_seed(seeds: [u64; 4]) -> Self {
+        let [k0, k1, k2, k3] = seeds;
+        Self::with_hasher(RandomState::with_seeds(k0, k1, k2, k3))
+    }
+
+    /// Constructs a new [Repository] whose first arena chunks live in `buf`,
+    /// so a small vocabulary is interned without allocating memory for strings.
+    ///
+    /// `buf` is split evenly among the internal buckets of the `Repository`,
+    /// and a string is stored in the part of the bucket it belongs to.
+    /// Once the part of a bucket is exhausted, that bucket allocates chunks as usual.
+    /// A part too small to hold a chunk is not used.
+    /// Hash tables are still allocated on the heap.
+    ///
+    /// `buf` is borrowed for `'static`, so it outlives the `Repository`, which never frees it.
+    /// [Repository::allocated_memory] does not count it.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let buf = Box::leak(vec![0; 64 * 1024].into_boxed_slice());
+    /// let repository = Repository::with_initial_buffer(buf);
+    /// let hello = ScopedSto::intern_in("hello", &repository);
+    /// assert_eq!(hello, "hello");
+    /// ```
+    pub fn with_initial_buffer(buf: &'static mut [u8]) -> Self {
+        let repository = Self::new();
+        let part_len = buf.len() / BUCKET_NUMBER;
+        if part_len > 0 {
+            for (bucket, part) in repository.buckets.iter().zip(buf.chunks_mut(part_len)) {
+                bucket.0.lock().arena = Arena::with_buffer(part);
+            }
+        }
+        repository
+    }
+
+    pub(crate) fn with_config(config: Config) -> Self {
+        Self::with_config_and_hasher(config, default_hash_builder())
+    }
+}
+
+impl<H: BuildHasher> Repository<BUCKET_NUMBER, H> {
+    /// Constructs a new [Repository] which hashes strings with `hasher`.
+    ///
+    /// Handles of the same string from repositories with different hashers
+    /// can still be compared by [ScopedSto::as_str].
+    ///
+    /// ## Example
+    /// ```
+    /// # use std::collections::hash_map::RandomState;
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::with_hasher(RandomState::new());
+    /// let a = ScopedSto::intern_in("hello", &repository);
+    /// let b = ScopedSto::intern_in("hello", &repository);
+    /// assert_eq!(a, b);
+    /// ```
+    pub fn with_hasher(hasher: H) -> Self {
+        Self::with_config_and_hasher(Config::default(), hasher)
+    }
 }
 
-impl Repository {
-    /// Constructs a new [Repository].
+impl<const BUCKETS: usize> Repository<BUCKETS> {
+    /// Constructs a new [Repository] with `BUCKETS` buckets,
+    /// instead of the 64 buckets of [Repository::new].
+    ///
+    /// Every bucket has its own lock, hash table and arena.
+    /// Fewer buckets waste less memory for a small `Repository`,
+    /// and more buckets reduce lock contention among many threads.
+    ///
+    /// ## Panics
+    /// Panics if `BUCKETS` is not a power of two.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::<4>::with_buckets();
+    /// let hello = ScopedSto::intern_in("hello", &repository);
+    /// assert_eq!(repository.get("hello"), Some(hello));
+    /// ```
+    pub fn with_buckets() -> Self {
+        Self::with_config_and_hasher(Config::default(), default_hash_builder())
+    }
+}
+
+impl<const BUCKETS: usize, H: BuildHasher> Repository<BUCKETS, H> {
+    pub(crate) fn with_config_and_hasher(config: Config, hasher: H) -> Self {
+        assert!(
+            BUCKETS.is_power_of_two(),
+            "the number of buckets must be a power of two"
+        );
+        Self {
+            buckets: [(); BUCKETS].map(|_| Bucket::new(&config)),
+            timing: Timing::default(),
+            ids: Mutex::default(),
+            bytes: Bucket::new(&config),
+            pairs: Bucket::new(&config),
+            config,
+            hasher,
+        }
+    }
+
+    /// Returns the number of interned strings.
+    ///
+    /// Buckets are locked one at a time, so the result may be stale
+    /// while other threads are interning.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// ScopedSto::intern_in("hello", &repository);
+    /// ScopedSto::intern_in("hello", &repository);
+    /// assert_eq!(repository.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.buckets.iter().map(|b| b.0.read().entries.len()).sum()
+    }
+
+    /// Returns the total number of slots in the hash tables of all buckets,
+    /// where an unallocated table has none.
+    ///
+    /// The ratio of [Repository::len] to it tells how much of the tables is unused,
+    /// e.g. to decide when to call [Repository::shrink_to_fit].
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// assert_eq!(repository.capacity(), 0);
+    /// ScopedSto::intern_in("hello", &repository);
+    /// assert!(repository.capacity() > repository.len());
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.buckets
+            .iter()
+            .map(|b| b.0.read())
+            .filter(|b| b.entries.allocated())
+            .map(|b| b.entries.capacity())
+            .sum()
+    }
+
+    /// Returns the fraction of slots holding interned strings in the allocated hash tables,
+    /// or 0 if no table is allocated.
+    ///
+    /// It never exceeds the load factor set by [RepositoryBuilder::load_factor].
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::with_load_factor(1, 2);
+    /// assert_eq!(repository.load_factor(), 0.0);
+    /// for i in 0..10000 {
+    ///     ScopedSto::intern_in(i.to_string(), &repository);
+    /// }
+    /// assert!(repository.load_factor() <= 0.5);
+    /// ```
+    pub fn load_factor(&self) -> f64 {
+        let (len, capacity) = self
+            .buckets
+            .iter()
+            .map(|b| b.0.read())
+            .filter(|b| b.entries.allocated())
+            .fold((0, 0), |(len, capacity), b| {
+                (len + b.entries.len(), capacity + b.entries.capacity())
+            });
+        if capacity == 0 {
+            0.0
+        } else {
+            len as f64 / capacity as f64
+        }
+    }
+
+    /// Returns `true` if no string is interned.
+    pub fn is_empty(&self) -> bool {
+        self.buckets.iter().all(|b| b.0.read().entries.len() == 0)
+    }
+
+    /// Returns an iterator over all interned strings, in no particular order.
+    ///
+    /// The iterator locks one bucket at a time, and holds the lock while yielding
+    /// the strings of that bucket. Interning into the same [Repository] on the same thread
+    /// during the iteration may deadlock.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// ScopedSto::intern_in("hello", &repository);
+    /// ScopedSto::intern_in("world", &repository);
+    ///
+    /// let mut strings = repository.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+    /// strings.sort_unstable();
+    /// assert_eq!(strings, ["hello", "world"]);
+    /// ```
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            buckets: self.buckets.iter(),
+            guard: None,
+            pos: 0,
+        }
+    }
+
+    /// Returns the number of bytes allocated by the [Repository].
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// ScopedSto::intern_in("hello", &repository);
+    ///
+    /// let allocated_memory = repository.allocated_memory();
+    /// println!("Allocated memory: {} bytes", allocated_memory);
+    /// ```
+    pub fn allocated_memory(&self) -> usize {
+        self.buckets
+            .iter()
+            .chain([&self.bytes, &self.pairs])
+            .map(|b| b.0.read().allocated_memory())
+            .sum()
+    }
+
+    /// Returns a breakdown of the memory allocated by the [Repository].
+    ///
+    /// All buckets are visited in a single pass.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// ScopedSto::intern_in("hello", &repository);
+    ///
+    /// let stats = repository.memory_stats();
+    /// assert_eq!(stats.entry_count, 1);
+    /// assert_eq!(stats.chunk_count, 1);
+    /// assert_eq!(stats.total_bytes(), repository.allocated_memory());
+    /// ```
+    pub fn memory_stats(&self) -> MemoryStats {
+        let mut stats = MemoryStats::default();
+        for bucket in self.buckets.iter().chain([&self.bytes, &self.pairs]) {
+            let bucket = bucket.0.read();
+            stats.arena_bytes += bucket.arena.allocated_memory();
+            stats.entries_bytes += bucket.entries.allocated_memory();
+            stats.chunk_count += bucket.arena.chunk_count();
+            stats.entry_count += bucket.entries.len();
+            stats.largest_chunk_bytes = stats.largest_chunk_bytes.max(bucket.arena.largest_chunk());
+            stats.wasted_bytes += bucket.arena.wasted_bytes();
+        }
+        stats
+    }
+
+    /// Returns the statistics of every bucket, in the order of their indices.
+    ///
+    /// Strings are distributed to buckets by the highest bits of their hashes, and every bucket
+    /// has its own lock. Uneven entry counts reveal that a few buckets are contended hotspots.
+    /// Interned bytes are stored separately and not included.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// (0..1000).for_each(|i| {
+    ///     ScopedSto::intern_in(i.to_string(), &repository);
+    /// });
+    ///
+    /// let stats = repository.bucket_stats();
+    /// assert_eq!(stats.len(), 64);
+    /// assert_eq!(stats.iter().map(|s| s.entry_count).sum::<usize>(), 1000);
+    /// let max = stats.iter().map(|s| s.entry_count).max().unwrap();
+    /// assert!(max < 1000 / 64 * 3);
+    /// ```
+    pub fn bucket_stats(&self) -> Vec<BucketStat> {
+        self.buckets
+            .iter()
+            .enumerate()
+            .map(|(index, bucket)| {
+                let bucket = bucket.0.read();
+                BucketStat {
+                    index,
+                    entry_count: bucket.entries.len(),
+                    capacity: if bucket.entries.allocated() {
+                        bucket.entries.capacity()
+                    } else {
+                        0
+                    },
+                    arena_bytes: bucket.arena.allocated_memory(),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the average number of bytes allocated per interned string,
+    /// including both the arena and the hash table overhead.
+    ///
+    /// Only the memory of strings is counted, so unlike [Repository::allocated_memory],
+    /// the memory of interned bytes is excluded. All buckets are visited in a single pass.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// assert_eq!(repository.avg_bytes_per_entry(), 0.0);
+    ///
+    /// ScopedSto::intern_in("hello", &repository);
+    /// let avg = repository.avg_bytes_per_entry();
+    /// assert!(avg > "hello".len() as f64);
+    ///
+    /// repository.intern_bytes_in(b"\xFFhello");
+    /// assert_eq!(repository.avg_bytes_per_entry(), avg);
+    /// assert!(repository.allocated_memory() as f64 > avg);
+    /// ```
+    pub fn avg_bytes_per_entry(&self) -> f64 {
+        let (bytes, count) = self.buckets.iter().fold((0, 0), |(bytes, count), b| {
+            let b = b.0.read();
+            (bytes + b.allocated_memory(), count + b.entries.len())
+        });
+        bytes as f64 / count.max(1) as f64
+    }
+
+    /// Checks whether interning `string` as a new entry would allocate a chunk dedicated to it,
+    /// instead of using the shared chunks of the configured chunk capacity,
+    /// see [RepositoryBuilder::chunk_capacity].
+    ///
+    /// The padding required by [RepositoryBuilder::string_alignment] is taken into account.
+    ///
+    /// This does not intern the string, nor check whether it is already interned.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::Repository;
+    /// let repository = Repository::new();
+    /// assert!(!repository.would_allocate_dedicated_chunk("hello"));
+    /// assert!(repository.would_allocate_dedicated_chunk(&"hello".repeat(10_000)));
+    /// ```
+    pub fn would_allocate_dedicated_chunk(&self, string: &str) -> bool {
+        Arena::needs_dedicated_chunk(string.len(), &self.config)
+    }
+
+    /// Returns the interned string if it exists, without interning it.
+    ///
+    /// A miss neither allocates memory nor changes the [Repository].
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let hello = ScopedSto::intern_in("hello", &repository);
+    /// assert_eq!(repository.get("hello"), Some(hello));
+    /// assert_eq!(repository.get("world"), None);
+    /// ```
+    pub fn get(&self, string: &str) -> Option<ScopedSto<'_>> {
+        self.find(string).map(ScopedSto::new)
+    }
+
+    /// Checks whether a string has been interned, without interning it.
+    ///
+    /// Only the bucket of the string is locked, and a miss does not change the [Repository].
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// ScopedSto::intern_in("hello", &repository);
+    /// assert!(repository.contains("hello"));
+    /// assert!(!repository.contains("world"));
+    /// ```
+    pub fn contains(&self, string: &str) -> bool {
+        self.find(string).is_some()
+    }
+
+    /// Checks whether a handle comes from this [Repository].
+    ///
+    /// Unlike [Repository::contains], a handle of an equal string interned in another `Repository`
+    /// is not contained. The stored hash of the handle is reused, so the string is not hashed again.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let other = Repository::new();
+    /// assert!(repository.contains_sto(ScopedSto::intern_in("hello", &repository)));
+    /// assert!(!repository.contains_sto(ScopedSto::intern_in("hello", &other)));
+    /// ```
+    pub fn contains_sto(&self, sto: ScopedSto<'_>) -> bool {
+        let hash = sto.hash();
+        self.buckets[Self::determine_bucket(hash)]
+            .0
+            .read()
+            .entries
+            .get(hash, sto.as_str().as_bytes())
+            == Some(sto.entry)
+    }
+
+    /// Looks up many strings at once without interning them.
+    ///
+    /// The queries are grouped by bucket so each bucket is locked only once.
+    /// Strings that have not been interned are absent from the returned map.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let hello = ScopedSto::intern_in("hello", &repository);
+    ///
+    /// let found = repository.get_many(&["hello", "world"]);
+    /// assert_eq!(found.len(), 1);
+    /// assert_eq!(found["hello"], hello);
+    /// ```
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn get_many<'s>(&self, strings: &[&'s str]) -> HashMap<&'s str, ScopedSto<'_>> {
+        let mut queries = strings
+            .iter()
+            .map(|&string| {
+                let hash = self.hash_of(string);
+                (Self::determine_bucket(hash), hash, string)
+            })
+            .collect::<Vec<_>>();
+        queries.sort_unstable_by_key(|&(bucket, _, _)| bucket);
+
+        let mut found = HashMap::new();
+        let mut rest = &queries[..];
+        while let Some(&(bucket, _, _)) = rest.first() {
+            let len = rest.iter().take_while(|q| q.0 == bucket).count();
+            let (group, remaining) = rest.split_at(len);
+            rest = remaining;
+
+            let b = self.buckets[bucket].0.read();
+            for &(_, hash, string) in group {
+                if let Some(entry) = b.entries.get(hash, string.as_bytes()) {
+                    found.insert(string, ScopedSto::new(entry));
+                }
+            }
+        }
+        found
+    }
+
+    /// Reports pairs of distinct interned strings sharing the same 64-bit hash.
+    ///
+    /// Collisions are handled correctly by the `Repository`, but frequent collisions
+    /// indicate that the hasher does not fit the data well.
+    ///
+    /// This walks every bucket once and groups the entries in a temporary `HashMap`,
+    /// so it costs O(n) time and memory.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn collisions(&self) -> Vec<(ScopedSto<'_>, ScopedSto<'_>)> {
+        let mut by_hash: HashMap<u64, Vec<Entry>> = HashMap::new();
+        self.for_each_entry(|entry| by_hash.entry(entry.hash()).or_default().push(entry));
+
+        let mut pairs = Vec::new();
+        for group in by_hash.values().filter(|group| group.len() > 1) {
+            for (i, &a) in group.iter().enumerate() {
+                for &b in &group[i + 1..] {
+                    pairs.push((ScopedSto::new(a), ScopedSto::new(b)));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Interns a string and returns the [ChunkId] of the arena chunk it is stored in.
+    ///
+    /// Sorting handles by their `ChunkId` lets strings from the same chunk be processed
+    /// consecutively. Finding the chunk of an existing string walks the chunks of its bucket.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::Repository;
+    /// let repository = Repository::new();
+    /// let (a, chunk_a) = repository.intern_in_with_chunk("hello");
+    /// let (b, chunk_b) = repository.intern_in_with_chunk("hello");
+    /// assert_eq!(a, b);
+    /// assert_eq!(chunk_a, chunk_b);
+    /// ```
+    pub fn intern_in_with_chunk<S>(&self, string: S) -> (ScopedSto<'_>, ChunkId)
+    where
+        S: AsRef<str>,
+    {
+        let string = string.as_ref();
+        let mut stopwatch = Stopwatch::start();
+        let hash = self.hash_of(string);
+        let hash_nanos = stopwatch.lap();
+        let mut bucket = self.buckets[Self::determine_bucket(hash)].0.lock();
+        let entry = self
+            .try_insert_locked(&mut bucket, hash, hash_nanos, string, |dest| unsafe {
+                copy_nonoverlapping(string.as_ptr(), dest, string.len())
+            })
+            .unwrap_or_else(|err| panic!("{err}"))
+            .entry;
+        let chunk = bucket
+            .arena
+            .chunk_of(entry.0.as_ptr())
+            .expect("internal error");
+        (ScopedSto::new(entry), chunk)
+    }
+
+    /// Copies all interned strings, bytes and pairs into a new, independent [Repository]
+    /// with the same configuration.
+    ///
+    /// The strings are interned again into the new `Repository`, so handles of this `Repository`
+    /// are not handles of the copy, and should not be compared with handles of the copy directly.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let a = ScopedSto::intern_in("hello", &repository);
+    ///
+    /// let copy = repository.clone_contents();
+    /// let b = ScopedSto::intern_in("hello", &copy);
+    /// assert_eq!(a.as_str(), b.as_str());
+    /// ```
+    pub fn clone_contents(&self) -> Repository<BUCKETS, H>
+    where
+        H: Clone,
+    {
+        let mut copy = Self::with_config_and_hasher(self.config.clone(), self.hasher.clone());
+        self.for_each_entry(|entry| {
+            copy.copy_in(entry.as_str());
+        });
+
+        // the hasher is the same, so the hashes are reused
+        let bytes = copy.bytes.0.get_mut();
+        for entry in self.bytes.0.read().entries.iter() {
+            bytes.get_or_insert(entry.hash(), entry.as_bytes(), &copy.config);
+        }
+
+        // the components are copied already, so pairs are keyed on their copies
+        for pair in self.pairs.0.read().entries.iter() {
+            let (first, second) = pair_entries(pair.as_bytes());
+            let find = |entry: Entry| copy.find(entry.as_str()).expect("internal error");
+            let key = pair_key(find(first), find(second));
+            let hash = copy.hash_bytes(&key);
+            copy.pairs.0.lock().get_or_insert(hash, &key, &copy.config);
+        }
+        copy
+    }
+
+    /// Interns all strings and bytes of `other` into this [Repository],
+    /// and returns the number of newly interned ones.
+    ///
+    /// The contents are copied, so handles of `other` stay handles of `other`.
+    /// Only one bucket is locked at a time, so merging two repositories into each other
+    /// concurrently does not deadlock.
+    /// With the feature `refcount`, no reference is added to the strings interned already.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// ScopedSto::intern_in("hello", &repository);
+    ///
+    /// let other = Repository::new();
+    /// ScopedSto::intern_in("hello", &other);
+    /// ScopedSto::intern_in("world", &other);
+    ///
+    /// assert_eq!(repository.merge(&other), 1);
+    /// assert_eq!(repository.len(), 2);
+    /// ```
+    pub fn merge<const OTHER_BUCKETS: usize, O>(
+        &self,
+        other: &Repository<OTHER_BUCKETS, O>,
+    ) -> usize
+    where
+        O: BuildHasher,
+    {
+        let mut merged = 0;
+        let mut entries = Vec::new();
+        for bucket in &other.buckets {
+            entries.extend(bucket.0.read().entries.iter());
+            for entry in entries.drain(..) {
+                merged += self.copy_in(entry.as_str()) as usize;
+            }
+        }
+
+        entries.extend(other.bytes.0.read().entries.iter());
+        for entry in entries {
+            let bytes = entry.as_bytes();
+            let hash = self.hash_bytes(bytes);
+            merged += self
+                .bytes
+                .0
+                .lock()
+                .get_or_insert(hash, bytes, &self.config)
+                .inserted as usize;
+        }
+        merged
+    }
+
+    /// Grows the hash tables, so about `additional` more strings can be interned
+    /// without growing them again.
+    ///
+    /// Like [Repository::with_capacity], `additional` is divided evenly among buckets,
+    /// and a hash table is only grown if its share does not fit, to the smallest capacity
+    /// holding it.
+    ///
+    /// ## Panics
+    /// Panics if the hash tables can not be allocated.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::Repository;
+    /// let mut repository = Repository::new();
+    /// repository.intern_grow_aware("hello");
+    /// repository.reserve(100_000);
+    /// assert!((0..100_000).all(|i| !repository.intern_grow_aware(i.to_string()).1));
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        if additional == 0 {
+            return;
+        }
+        let per_bucket = (additional - 1) / BUCKETS + 1;
+        for bucket in &mut self.buckets {
+            bucket
+                .0
+                .get_mut()
+                .entries
+                .reserve(per_bucket)
+                .unwrap_or_else(|err| panic!("{err}"));
+        }
+    }
+
+    /// Removes all interned strings and frees their memory, so the [Repository] can be reused.
+    ///
+    /// It takes `&mut self`, so no [ScopedSto] of this `Repository` can be alive.
+    /// Afterward, [Repository::allocated_memory] returns 0.
+    /// Chunks over the buffer of [Repository::with_initial_buffer] are reused rather than freed.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let mut repository = Repository::new();
+    /// ScopedSto::intern_in("hello", &repository);
+    ///
+    /// repository.clear();
+    /// assert!(repository.is_empty());
+    /// assert_eq!(repository.allocated_memory(), 0);
+    /// ```
+    pub fn clear(&mut self) {
+        for bucket in self
+            .buckets
+            .iter_mut()
+            .chain([&mut self.bytes, &mut self.pairs])
+        {
+            bucket.0.get_mut().clear(&self.config);
+        }
+        self.ids.get_mut().clear();
+    }
+
+    /// Same as [Repository::clear], but through a shared reference.
+    ///
+    /// # Safety
+    /// No handle of this `Repository` may be used afterward.
+    #[cfg(feature = "testing")]
+    pub(crate) unsafe fn clear_shared(&self) {
+        for bucket in self.buckets.iter().chain([&self.bytes, &self.pairs]) {
+            bucket.0.lock().clear(&self.config);
+        }
+        self.ids.lock().clear();
+    }
+
+    /// Removes all interned strings which do not satisfy the predicate `f`,
+    /// and returns the number of removed strings.
+    ///
+    /// It takes `&mut self`, so no [ScopedSto] of this `Repository` can be alive.
+    /// The retained strings of each bucket are copied into a new arena and a new hash table
+    /// of the same capacity, and the memory of removed strings is released.
+    /// Call [Repository::shrink_to_fit] to shrink the hash tables too.
+    /// Interned pairs are kept only if both of their components are retained.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let mut repository = Repository::new();
+    /// ScopedSto::intern_in("tmp_a", &repository);
+    /// ScopedSto::intern_in("b", &repository);
+    ///
+    /// assert_eq!(repository.retain(|s| !s.starts_with("tmp_")), 1);
+    /// assert_eq!(repository.to_sorted_vec(), ["b"]);
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F) -> usize
+    where
+        F: FnMut(&str) -> bool,
+    {
+        let mut removed = 0;
+        let mut rebuilt = Vec::with_capacity(BUCKETS);
+        for bucket in &mut self.buckets {
+            let bucket = bucket.0.get_mut();
+            let mut retained = BucketImpl {
+                arena: Arena::default(),
+                entries: bucket.entries.empty_like(),
+            };
+            for entry in bucket.entries.iter() {
+                if f(entry.as_str()) {
+                    retained.get_or_insert(entry.hash(), entry.as_bytes(), &self.config);
+                } else {
+                    removed += 1;
+                }
+            }
+            rebuilt.push(retained);
+        }
+        self.replace_buckets(rebuilt);
+        removed
+    }
+
+    /// Removes all interned strings except those in `live`, and returns the number of freed bytes.
+    ///
+    /// Unlike [Repository::retain], the memory of removed strings is released: the strings
+    /// in `live` are copied into new arena chunks, and the old chunks are freed.
+    /// It takes `&mut self`, so no [ScopedSto] of this `Repository` can be alive,
+    /// and the live strings are passed by contents. Strings in `live` which are not interned
+    /// are ignored, and interned bytes are kept, as well as pairs of live strings.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let mut repository = Repository::new();
+    /// for i in 0..10_000 {
+    ///     ScopedSto::intern_in(format!("request-{i}"), &repository);
+    /// }
+    ///
+    /// let freed = repository.collect(["request-0", "request-1"]);
+    /// assert!(freed > 0);
+    /// assert_eq!(repository.to_sorted_vec(), ["request-0", "request-1"]);
+    /// ```
+    pub fn collect<I>(&mut self, live: I) -> usize
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let before = self.allocated_memory();
+        let mut rebuilt = Vec::with_capacity(BUCKETS);
+        rebuilt.resize_with(BUCKETS, || BucketImpl::new(&self.config));
+        for string in live {
+            let bytes = string.as_ref().as_bytes();
+            let hash = self.hash_bytes(bytes);
+            let index = Self::determine_bucket(hash);
+            let bucket = self.buckets[index].0.get_mut();
+            if bucket.entries.get(hash, bytes).is_some() {
+                rebuilt[index].get_or_insert(hash, bytes, &self.config);
+            }
+        }
+
+        self.replace_buckets(rebuilt);
+        before.saturating_sub(self.allocated_memory())
+    }
+
+    /// Replaces the string buckets with `rebuilt` ones holding copies of some of their strings,
+    /// and drops the ids and pairs of the strings which are not copied.
+    fn replace_buckets(&mut self, rebuilt: Vec<BucketImpl>) {
+        // the old entries are still readable until their buckets are replaced
+        let find = |entry: Entry| {
+            let hash = entry.hash();
+            rebuilt[Self::determine_bucket(hash)]
+                .entries
+                .get(hash, entry.as_bytes())
+        };
+        let pairs = self.pairs.0.get_mut();
+        let mut kept = BucketImpl {
+            arena: Arena::default(),
+            entries: pairs.entries.empty_like(),
+        };
+        for pair in pairs.entries.iter() {
+            let (first, second) = pair_entries(pair.as_bytes());
+            if let (Some(first), Some(second)) = (find(first), find(second)) {
+                let key = pair_key(first, second);
+                kept.get_or_insert(hash_with(&self.hasher, &key), &key, &self.config);
+            }
+        }
+        *pairs = kept;
+
+        if self.config.indexed {
+            let ids = self.ids.get_mut();
+            *ids = ids.iter().filter_map(|&entry| find(entry)).collect();
+            for (id, entry) in ids.iter().enumerate() {
+                unsafe { entry.set_id(id as u32) };
+            }
+        }
+        for (bucket, rebuilt) in self.buckets.iter_mut().zip(rebuilt) {
+            *bucket.0.get_mut() = rebuilt;
+        }
+    }
+
+    /// Shrinks the hash table of a single bucket to the smallest capacity fitting its strings.
+    ///
+    /// Interned strings are distributed to buckets by their hashes, and a bucket may grow
+    /// much larger than others. This releases the over-allocated memory of that bucket only.
+    ///
+    /// ## Panics
+    /// Panics if `bucket` is out of range.
+    pub fn shrink_bucket(&mut self, bucket: usize) {
+        assert!(
+            bucket < BUCKETS,
+            "bucket index {bucket} out of range for {BUCKETS} buckets"
+        );
+        self.buckets[bucket].0.get_mut().entries.shrink_to_fit();
+    }
+
+    /// Shrinks the hash tables of all buckets to the smallest capacities fitting their strings,
+    /// and returns the number of freed bytes.
+    ///
+    /// This is useful after [Repository::retain] removed most strings,
+    /// as it keeps the capacities of the hash tables.
+    /// Only the hash tables are reallocated: interned strings can not move in the arena,
+    /// so its memory is kept.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let mut repository = Repository::new();
+    /// (0..1000).for_each(|i| {
+    ///     ScopedSto::intern_in(i.to_string(), &repository);
+    /// });
+    ///
+    /// repository.retain(|s| s.len() == 1);
+    /// assert!(repository.shrink_to_fit() > 0);
+    /// assert_eq!(repository.len(), 10);
+    /// ```
+    pub fn shrink_to_fit(&mut self) -> usize {
+        self.buckets
+            .iter_mut()
+            .chain([&mut self.bytes, &mut self.pairs])
+            .map(|b| b.0.get_mut().entries.shrink_to_fit())
+            .sum()
+    }
+
+    /// Checks whether `string` points into the memory of this [Repository].
+    ///
+    /// This is useful to assert that a `&str` comes from [ScopedSto::as_str] of a handle of this
+    /// `Repository`. It walks all arena chunks, so it costs O(chunks).
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let interned = ScopedSto::intern_in("hello", &repository);
+    /// assert!(repository.is_interned_ptr(interned.as_str()));
+    /// assert!(!repository.is_interned_ptr("hello"));
+    /// ```
+    pub fn is_interned_ptr(&self, string: &str) -> bool {
+        self.buckets
+            .iter()
+            .any(|b| b.0.read().arena.chunk_of(string.as_ptr()).is_some())
+    }
+
+    /// Interns the string collected from an iterator of characters.
+    ///
+    /// The characters are collected into a reusable thread-local buffer,
+    /// so no `String` is allocated for each call.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let a = repository.intern_chars("hello".chars());
+    /// assert_eq!(a, ScopedSto::intern_in("hello", &repository));
+    /// ```
+    pub fn intern_chars<I>(&self, chars: I) -> ScopedSto<'_>
+    where
+        I: IntoIterator<Item = char>,
+    {
+        with_scratch(|buf| {
+            buf.extend(chars);
+            ScopedSto::intern_in(&*buf, self)
+        })
+    }
+
+    /// Interns the NFC normalization of a string, so canonically equivalent strings,
+    /// e.g. `"café"` in NFC and NFD, are interned once.
+    ///
+    /// The interned string is normalized, so [ScopedSto::as_str] returns the NFC form.
+    /// A string which is not NFC yet is normalized into a reusable thread-local buffer,
+    /// which may allocate if the buffer is too small. The buffer is not used if the string
+    /// is quickly detected to be NFC already, which is the case for ASCII strings.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let nfc = repository.intern_normalized("caf\u{e9}");
+    /// let nfd = repository.intern_normalized("cafe\u{301}");
+    /// assert_eq!(nfc, nfd);
+    /// assert_eq!(nfd.as_str(), "caf\u{e9}");
+    /// ```
+    #[cfg(feature = "unicode")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "unicode")))]
+    pub fn intern_normalized(&self, string: &str) -> ScopedSto<'_> {
+        use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+        if is_nfc_quick(string.chars()) == IsNormalized::Yes {
+            return ScopedSto::intern_in(string, self);
+        }
+        with_scratch(|buf| {
+            buf.extend(string.nfc());
+            ScopedSto::intern_in(&*buf, self)
+        })
+    }
+
+    /// Interns the concatenation of `a` and `b`.
+    ///
+    /// They are joined in a reusable thread-local buffer rather than a new `String`,
+    /// since the hash of the whole string is needed to find it.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let a = repository.intern_concat("hello ", "world");
+    /// assert_eq!(a, ScopedSto::intern_in("hello world", &repository));
+    /// ```
+    pub fn intern_concat(&self, a: &str, b: &str) -> ScopedSto<'_> {
+        with_scratch(|buf| {
+            buf.push_str(a);
+            buf.push_str(b);
+            ScopedSto::intern_in(&*buf, self)
+        })
+    }
+
+    /// Interns the substring of `parent` in `range`.
+    ///
+    /// The substring is copied into the arena, so it is deduplicated like any other string.
+    ///
+    /// ## Panics
+    /// Panics like `&str` slicing if `range` is out of bounds,
+    /// or does not lie on UTF-8 character boundaries of `parent`.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let line = ScopedSto::intern_in("key = value", &repository);
+    /// let key = repository.intern_substr(line, 0..3);
+    /// assert_eq!(key, ScopedSto::intern_in("key", &repository));
+    /// ```
+    pub fn intern_substr(&self, parent: ScopedSto<'_>, range: Range<usize>) -> ScopedSto<'_> {
+        ScopedSto::intern_in(&parent.as_str()[range], self)
+    }
+
+    /// Interns a string and reports whether the call made the hash table of a bucket grow.
+    ///
+    /// Growing rehashes all entries of the bucket, which is the main source of latency spikes
+    /// when interning. See [RepositoryBuilder::max_probe_length] for early growth.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::Repository;
+    /// let repository = Repository::new();
+    /// // the first string allocates the hash table of its bucket
+    /// let (a, grew) = repository.intern_grow_aware("hello");
+    /// assert!(grew);
+    /// let (b, grew) = repository.intern_grow_aware("hello");
+    /// assert!(!grew);
+    /// assert_eq!(a, b);
+    /// ```
+    pub fn intern_grow_aware<S>(&self, string: S) -> (ScopedSto<'_>, bool)
+    where
+        S: AsRef<str>,
+    {
+        let outcome = self.lock_and_insert(string.as_ref());
+        (ScopedSto::new(outcome.entry), outcome.grew)
+    }
+
+    /// Interns a string, and returns whether it is newly inserted by this call.
+    ///
+    /// See also [ScopedSto::intern_in_full].
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::Repository;
+    /// let repository = Repository::new();
+    /// let (hello, inserted) = repository.get_or_insert_full("hello");
+    /// assert!(inserted);
+    /// assert_eq!(repository.get_or_insert_full("hello"), (hello, false));
+    /// ```
+    pub fn get_or_insert_full(&self, string: &str) -> (ScopedSto<'_>, bool) {
+        let outcome = self.lock_and_insert(string);
+        (ScopedSto::new(outcome.entry), outcome.inserted)
+    }
+
+    /// Returns the hash of `string`, exactly the one computed when it is interned
+    /// and returned by [ScopedSto::hash].
+    ///
+    /// The hash depends on the hasher of the [Repository], see [Repository::with_seed]
+    /// and [Repository::with_hasher]. It can be passed to [Repository::intern_with_hash] later.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let hash = repository.hash_of("hello");
+    /// assert_eq!(ScopedSto::intern_in("hello", &repository).hash(), hash);
+    /// assert_eq!(repository.intern_with_hash(hash, "hello"), "hello");
+    /// ```
+    pub fn hash_of(&self, string: &str) -> u64 {
+        hash_with(&self.hasher, string.as_bytes())
+    }
+
+    /// Interns a string, or returns an error instead of panicking if it can not be stored,
+    /// because the allocator fails or the string is too large.
+    ///
+    /// [ScopedSto::intern_in] panics in these cases.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::Repository;
+    /// let repository = Repository::new();
+    /// let hello = repository.try_intern_in("hello").unwrap();
+    /// assert_eq!(hello, "hello");
+    /// ```
+    pub fn try_intern_in(&self, string: &str) -> Result<ScopedSto<'_>, InternError> {
+        let mut stopwatch = Stopwatch::start();
+        let hash = self.hash_of(string);
+        let hash_nanos = stopwatch.lap();
+        let outcome = self.try_lock_and_insert_hashed(hash, hash_nanos, string, |dest| unsafe {
+            copy_nonoverlapping(string.as_ptr(), dest, string.len())
+        })?;
+        Ok(ScopedSto::new(outcome.entry))
+    }
+
+    /// Interns a string literal, or any `&'static str`, without copying it on a miss.
+    ///
+    /// The [Repository] stores a pointer to `string` instead of its bytes, which saves memory
+    /// for long literals. Strings are still deduplicated by content, so a handle of a copy
+    /// interned earlier is returned if any.
+    ///
+    /// A borrowed string is not aligned to [RepositoryBuilder::string_alignment],
+    /// and is not in the memory of the `Repository` for [Repository::is_interned_ptr].
+    /// With a custom alignment or a sentinel, `string` is copied like [ScopedSto::intern_in].
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// static LITERAL: &str = "hello";
+    /// let hello = repository.intern_static(LITERAL);
+    /// assert_eq!(hello.as_str().as_ptr(), LITERAL.as_ptr());
+    /// assert_eq!(hello, ScopedSto::intern_in("hello", &repository));
+    /// ```
+    pub fn intern_static(&self, string: &'static str) -> ScopedSto<'_> {
+        if self.config.sentinel.is_some() || self.config.string_alignment != ALLOC_ALIGNMENT {
+            return ScopedSto::intern_in(string, self);
+        }
+
+        let mut stopwatch = Stopwatch::start();
+        let hash = self.hash_of(string);
+        let hash_nanos = stopwatch.lap();
+        let bucket = &self.buckets[Self::determine_bucket(hash)].0;
+        let outcome = self
+            .try_insert_locked_by(&mut bucket.lock(), hash, hash_nanos, string, |arena| {
+                let ptr = arena.try_alloc(hash, size_of::<usize>(), &self.config)?;
+                unsafe { Entry::write_borrowed(ptr, string.as_bytes()) };
+                Ok(ptr)
+            })
+            .unwrap_or_else(|err| panic!("{err}"));
+        ScopedSto::new(outcome.entry)
+    }
+
+    /// Interns a string without blocking, or returns `None` if its bucket is locked by another thread.
+    ///
+    /// This is useful where blocking is not allowed, like a real-time thread, which can defer
+    /// the string to be interned later on `None`. It still allocates memory for a new string.
+    /// With [RepositoryBuilder::indexed], it may block shortly to assign an id to a new string.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let hello = repository.try_lock_intern_in("hello").unwrap();
+    /// assert_eq!(hello, ScopedSto::intern_in("hello", &repository));
+    /// ```
+    pub fn try_lock_intern_in(&self, string: &str) -> Option<ScopedSto<'_>> {
+        let mut stopwatch = Stopwatch::start();
+        let hash = self.hash_of(string);
+        let hash_nanos = stopwatch.lap();
+        let bucket = &self.buckets[Self::determine_bucket(hash)].0;
+
+        #[cfg(feature = "rwlock")]
+        {
+            let bucket = bucket.try_read()?;
+            if let Some(entry) = bucket.entries.get(hash, string.as_bytes()) {
+                #[cfg(feature = "refcount")]
+                entry.refcount().fetch_add(1, AtomicOrdering::Relaxed);
+                self.timing.record(hash_nanos, stopwatch.lap(), 0);
+                return Some(ScopedSto::new(entry));
+            }
+        }
+
+        let mut bucket = bucket.try_lock()?;
+        let outcome = self
+            .try_insert_locked(&mut bucket, hash, hash_nanos, string, |dest| unsafe {
+                copy_nonoverlapping(string.as_ptr(), dest, string.len())
+            })
+            .unwrap_or_else(|err| panic!("{err}"));
+        Some(ScopedSto::new(outcome.entry))
+    }
+
+    /// Interns a string, and returns the number of bytes allocated by the call.
+    ///
+    /// It is 0 if the string has been interned, or fits in the current arena chunk
+    /// and hash table. Otherwise it is the size of the new chunk, plus the growth of the hash table.
+    /// This is useful to apply back-pressure by memory usage without walking the whole
+    /// [Repository] by [Repository::allocated_memory].
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::Repository;
+    /// let repository = Repository::new();
+    /// let (hello, allocated) = repository.intern_in_tracked("hello");
+    /// assert_eq!(allocated, repository.allocated_memory());
+    /// assert_eq!(repository.intern_in_tracked("hello"), (hello, 0));
+    /// ```
+    pub fn intern_in_tracked(&self, string: &str) -> (ScopedSto<'_>, usize) {
+        let mut stopwatch = Stopwatch::start();
+        let hash = self.hash_of(string);
+        let hash_nanos = stopwatch.lap();
+
+        let mut bucket = self.buckets[Self::determine_bucket(hash)].0.lock();
+        let before = bucket.allocated_memory();
+        let outcome = self
+            .try_insert_locked(&mut bucket, hash, hash_nanos, string, |dest| unsafe {
+                copy_nonoverlapping(string.as_ptr(), dest, string.len())
+            })
+            .unwrap_or_else(|err| panic!("{err}"));
+        (
+            ScopedSto::new(outcome.entry),
+            bucket.allocated_memory() - before,
+        )
+    }
+
+    /// Interns a string with its precomputed hash, to avoid hashing it again.
+    ///
+    /// ## Warning
+    /// `hash` must be the hash computed by this [Repository] for `string`, that is,
+    /// [Repository::hash_of], or [ScopedSto::hash] of a handle of the same string
+    /// from a `Repository` with the same hasher.
+    /// Otherwise the string may be stored in a wrong place, so it is interned again
+    /// into another handle, and lookups by the string miss it. This is checked in debug builds.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let hello = ScopedSto::intern_in("hello", &repository);
+    ///
+    /// let copy = Repository::new();
+    /// let copied = copy.intern_with_hash(hello.hash(), "hello");
+    /// assert_eq!(copied, ScopedSto::intern_in("hello", &copy));
+    /// ```
+    pub fn intern_with_hash(&self, hash: u64, string: &str) -> ScopedSto<'_> {
+        debug_assert_eq!(hash, self.hash_of(string), "wrong hash of {string:?}");
+        let outcome = self.lock_and_insert_hashed(hash, 0, string, |dest| unsafe {
+            copy_nonoverlapping(string.as_ptr(), dest, string.len())
+        });
+        ScopedSto::new(outcome.entry)
+    }
+
+    /// Returns the interned `probe` if it exists,
+    /// otherwise interns the string produced by `make`.
+    ///
+    /// `make` is only called on a miss and outside the bucket lock,
+    /// so expensive work like canonicalization is deferred to misses.
+    ///
+    /// Note that the string produced by `make` is stored as it is.
+    /// If it differs from `probe`, later probes with the same `probe` still miss,
+    /// so this is most useful when canonical inputs are common.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::Repository;
+    /// let repository = Repository::new();
+    /// let a = repository.intern_or_else("hello", || "HELLO".to_lowercase().into());
+    /// // `make` is not called on a hit
+    /// let b = repository.intern_or_else("hello", || unreachable!());
+    /// assert_eq!(a, b);
+    /// ```
+    pub fn intern_or_else<'s, F>(&self, probe: &str, make: F) -> ScopedSto<'_>
+    where
+        F: FnOnce() -> Cow<'s, str>,
+    {
+        match self.find(probe) {
+            Some(entry) => ScopedSto::new(entry),
+            None => ScopedSto::intern_in(make(), self),
+        }
+    }
+
+    /// Interns a string in this [Repository], consulting a secondary `cold` `Repository` on a miss.
     ///
-    /// The newly constructed `Repository` does not allocate memory initially.
-    /// Memory allocation only occurs when there is a string to be interned.
+    /// The string is looked up in `self` first, then in `cold` without inserting into it.
+    /// A string found in `cold` is copied from it into `self`, so the returned handle
+    /// always belongs to `self`, and compares with other handles of `self` by pointers.
+    /// The string is hashed once by the hasher of `self`, for both the lookup and the insertion.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let hot = Repository::new();
+    /// let cold = Repository::new();
+    /// ScopedSto::intern_in("vocabulary", &cold);
     ///
+    /// let vocabulary = hot.intern_tiered("vocabulary", &cold);
+    /// assert_eq!(vocabulary, ScopedSto::intern_in("vocabulary", &hot));
+    /// ```
+    pub fn intern_tiered<S, const COLD_BUCKETS: usize, C>(
+        &self,
+        string: S,
+        cold: &Repository<COLD_BUCKETS, C>,
+    ) -> ScopedSto<'_>
+    where
+        S: AsRef<str>,
+        C: BuildHasher,
+    {
+        let string = string.as_ref();
+        let mut stopwatch = Stopwatch::start();
+        let hash = self.hash_of(string);
+        let hash_nanos = stopwatch.lap();
+        // the bucket is released before `cold` is locked, which may be `self`
+        let hit = self.buckets[Self::determine_bucket(hash)]
+            .0
+            .read()
+            .entries
+            .get(hash, string.as_bytes())
+            .is_some();
+        let source = match cold.find(string) {
+            Some(entry) if !hit => entry.as_str(),
+            _ => string,
+        };
+        let outcome = self.lock_and_insert_hashed(hash, hash_nanos, source, |dest| unsafe {
+            copy_nonoverlapping(source.as_ptr(), dest, source.len())
+        });
+        ScopedSto::new(outcome.entry)
+    }
+
+    /// Interns a string and folds `f` over its bytes, starting from `init`.
     ///
+    /// When the string is newly interned, the fold runs in the same pass that copies
+    /// the bytes into the arena, so the bytes are not scanned twice.
     ///
     /// ## Example
     /// ```
     /// # use sto::Repository;
     /// let repository = Repository::new();
+    /// let checksum = |acc: u32, b: u8| acc.wrapping_mul(31).wrapping_add(b as u32);
+    /// let (s, sum) = repository.intern_folding("hello", 0, checksum);
+    /// assert_eq!(sum, s.as_str().bytes().fold(0, checksum));
     /// ```
-    pub fn new() -> Self {
-        Self {
-            buckets: [(); BUCKET_NUMBER].map(|_| Bucket::default()),
-        }
+    pub fn intern_folding<S, R, F>(&self, string: S, init: R, mut f: F) -> (ScopedSto<'_>, R)
+    where
+        S: AsRef<str>,
+        F: FnMut(R, u8) -> R,
+    {
+        let string = string.as_ref();
+        let mut init = Some(init);
+        let mut folded = None;
+        let outcome = self.lock_and_insert_with(string, |dest| {
+            let init = init.take().expect("internal error");
+            folded = Some(string.bytes().enumerate().fold(init, |acc, (i, b)| {
+                unsafe { dest.add(i).write(b) };
+                f(acc, b)
+            }));
+        });
+        let folded = match folded {
+            Some(folded) => folded,
+            // the string has been interned, so nothing is copied
+            None => string
+                .bytes()
+                .fold(init.take().expect("internal error"), &mut f),
+        };
+        (ScopedSto::new(outcome.entry), folded)
     }
 
-    /// Returns the number of bytes allocated by the [Repository].
+    /// Interns a single character.
     ///
     /// ## Example
     /// ```
     /// # use sto::{Repository, ScopedSto};
     /// let repository = Repository::new();
-    /// ScopedSto::intern_in("hello", &repository);
+    /// let c = repository.intern_char('é');
+    /// assert_eq!(c, ScopedSto::intern_in("é", &repository));
+    /// ```
+    pub fn intern_char(&self, c: char) -> ScopedSto<'_> {
+        ScopedSto::intern_in(c.encode_utf8(&mut [0; 4]), self)
+    }
+
+    /// Interns a batch of strings, and groups the indices of the inputs which share a handle.
     ///
-    /// let allocated_memory = repository.allocated_memory();
-    /// println!("Allocated memory: {} bytes", allocated_memory);
+    /// The groups partition the input indices: every index is in exactly one group,
+    /// and distinct strings are in singleton groups. Groups are ordered by their first index,
+    /// and the indices in a group are ascending.
+    ///
+    /// ## Example
     /// ```
-    pub fn allocated_memory(&self) -> usize {
-        self.buckets
+    /// # use sto::Repository;
+    /// let repository = Repository::new();
+    /// let (stos, groups) = repository.intern_all_grouped(&["a", "b", "a", "c", "b"]);
+    /// assert_eq!(stos.len(), 5);
+    /// assert_eq!(groups, vec![vec![0, 2], vec![1, 4], vec![3]]);
+    /// ```
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn intern_all_grouped(&self, strings: &[&str]) -> (Vec<ScopedSto<'_>>, Vec<Vec<usize>>) {
+        let stos = strings
+            .iter()
+            .copied()
+            .map(self.interner())
+            .collect::<Vec<_>>();
+
+        let mut group_of = HashMap::with_capacity(stos.len());
+        let mut groups = Vec::<Vec<usize>>::new();
+        for (i, &sto) in stos.iter().enumerate() {
+            let group = *group_of.entry(sto).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[group].push(i);
+        }
+        (stos, groups)
+    }
+
+    /// Interns a string, storing integers in their canonical decimal form.
+    ///
+    /// If `string` is an integer, that is, ASCII digits with an optional leading `+` or `-`,
+    /// the `+` sign and leading zeros are removed, and `-0` becomes `0`, so `"007"`, `"+7"`
+    /// and `"7"` share a handle. The integer is canonicalized textually, so it may have
+    /// any number of digits. Other strings, including `""`, `"-"` and `"7.0"`, are interned verbatim.
+    ///
+    /// This is a specialized path: a string interned with [ScopedSto::intern_in] is never
+    /// canonicalized, so `"007"` may still be interned verbatim alongside `"7"`.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::Repository;
+    /// let repository = Repository::new();
+    /// let seven = repository.intern_numeric_canonical("007");
+    /// assert_eq!(seven, "7");
+    /// assert_eq!(seven, repository.intern_numeric_canonical("+7"));
+    /// assert_eq!(repository.intern_numeric_canonical("-00"), "0");
+    /// assert_eq!(repository.intern_numeric_canonical("007x"), "007x");
+    /// ```
+    pub fn intern_numeric_canonical(&self, string: &str) -> ScopedSto<'_> {
+        let (negative, digits) = match string.as_bytes().first() {
+            Some(b'-') => (true, &string[1..]),
+            Some(b'+') => (false, &string[1..]),
+            _ => (false, string),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return ScopedSto::intern_in(string, self);
+        }
+
+        let digits = match digits.trim_start_matches('0') {
+            "" => "0",
+            trimmed => trimmed,
+        };
+        if !negative || digits == "0" {
+            ScopedSto::intern_in(digits, self)
+        } else if digits.len() + 1 == string.len() {
+            ScopedSto::intern_in(string, self)
+        } else {
+            with_scratch(|buf| {
+                buf.push('-');
+                buf.push_str(digits);
+                ScopedSto::intern_in(&*buf, self)
+            })
+        }
+    }
+
+    /// Interns a string after `check` accepts it, or returns the error of `check`.
+    ///
+    /// If the string is already interned, it has been accepted before, so `check` is skipped.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::Repository;
+    /// let repository = Repository::new();
+    /// let is_ident = |s: &str| {
+    ///     if s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+    ///         Ok(())
+    ///     } else {
+    ///         Err(format!("invalid identifier: {s}"))
+    ///     }
+    /// };
+    /// assert_eq!(repository.intern_validated("foo_1", is_ident).unwrap(), "foo_1");
+    /// assert!(repository.intern_validated("foo-1", is_ident).is_err());
+    /// ```
+    pub fn intern_validated<E, F>(&self, string: &str, check: F) -> Result<ScopedSto<'_>, E>
+    where
+        F: FnOnce(&str) -> Result<(), E>,
+    {
+        if let Some(entry) = self.find(string) {
+            return Ok(ScopedSto::new(entry));
+        }
+        check(string)?;
+        Ok(ScopedSto::intern_in(string, self))
+    }
+
+    /// Interns a string which must not have been interned before.
+    ///
+    /// Returns [DuplicateError] with the existing handle if the string is already interned.
+    /// The check and the insertion are atomic under the lock of the bucket,
+    /// so among concurrent calls with the same string, exactly one succeeds.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::Repository;
+    /// let repository = Repository::new();
+    /// let main = repository.intern_unique("main").unwrap();
+    /// let err = repository.intern_unique("main").unwrap_err();
+    /// assert_eq!(err.existing(), main);
+    /// ```
+    pub fn intern_unique(&self, string: &str) -> Result<ScopedSto<'_>, DuplicateError<'_>> {
+        let outcome = self.lock_and_insert(string);
+        let sto = ScopedSto::new(outcome.entry);
+        if outcome.inserted {
+            Ok(sto)
+        } else {
+            Err(DuplicateError::new(sto))
+        }
+    }
+
+    /// Interns a batch of strings, and appends their handles to `out` in the input order.
+    ///
+    /// The strings are grouped by their buckets, so every bucket is locked only once,
+    /// and the strings of a bucket are interned together.
+    /// With [RepositoryBuilder::indexed], ids are assigned in the grouped order,
+    /// rather than the input order.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let mut stos = Vec::new();
+    /// repository.intern_many(&["b", "a", "b"], &mut stos);
+    /// assert_eq!(stos, ["b", "a", "b"]);
+    /// assert_eq!(stos[0], ScopedSto::intern_in("b", &repository));
+    /// ```
+    pub fn intern_many<'a>(&'a self, strings: &[&str], out: &mut Vec<ScopedSto<'a>>) {
+        let mut hashed = strings
             .iter()
             .enumerate()
-            .map(|(_, b)| {
-                let b = b.0.lock();
-                b.entries.allocated_memory() + b.arena.allocated_memory()
+            .map(|(i, string)| {
+                let mut stopwatch = Stopwatch::start();
+                let hash = self.hash_of(string);
+                (Self::determine_bucket(hash), hash, stopwatch.lap(), i)
             })
-            .sum()
+            .collect::<Vec<_>>();
+        hashed.sort_unstable_by_key(|&(bucket, ..)| bucket);
+
+        let mut entries = Vec::new();
+        entries.resize(strings.len(), None);
+        let mut rest = hashed.as_slice();
+        while let Some(&(bucket, ..)) = rest.first() {
+            let (group, next) = rest.split_at(rest.iter().take_while(|h| h.0 == bucket).count());
+            rest = next;
+
+            let mut bucket = self.buckets[bucket].0.lock();
+            for &(_, hash, hash_nanos, i) in group {
+                let string = strings[i];
+                let outcome = self
+                    .try_insert_locked(&mut bucket, hash, hash_nanos, string, |dest| unsafe {
+                        copy_nonoverlapping(string.as_ptr(), dest, string.len())
+                    })
+                    .unwrap_or_else(|err| panic!("{err}"));
+                entries[i] = Some(outcome.entry);
+            }
+        }
+
+        out.extend(
+            entries
+                .into_iter()
+                .map(|entry| ScopedSto::new(entry.expect("internal error"))),
+        );
+    }
+
+    /// Interns a batch of strings in sorted order, and returns their handles in the input order.
+    ///
+    /// The unique inputs which are not interned yet are stored in ascending order,
+    /// so later sorted iteration over them touches memory sequentially.
+    /// Strings are spread across the internal buckets by hash and every bucket has its own arena,
+    /// so the sorted layout holds among the strings of the same bucket, rather than the whole batch.
+    /// Strings interned later are not placed in sorted order.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let stos = repository.intern_batch_sorted(&["b", "a", "b"]);
+    /// assert_eq!(stos, ["b", "a", "b"]);
+    /// assert_eq!(stos[0], stos[2]);
+    /// ```
+    pub fn intern_batch_sorted(&self, strings: &[&str]) -> Vec<ScopedSto<'_>> {
+        let mut sorted = strings.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        let interned = sorted
+            .iter()
+            .copied()
+            .map(self.interner())
+            .collect::<Vec<_>>();
+
+        strings
+            .iter()
+            .map(|string| interned[sorted.binary_search(string).expect("internal error")])
+            .collect()
+    }
+
+    /// Interns the reversed-domain form of a domain name, like `com.example.www` for `www.example.com`,
+    /// so domains can be grouped hierarchically.
+    ///
+    /// The labels are reversed in a scratch buffer without allocating per label.
+    /// A single trailing dot of a fully qualified name is dropped, and empty labels are kept
+    /// in their reversed positions. IP addresses, like `127.0.0.1`, are interned verbatim.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::Repository;
+    /// let repository = Repository::new();
+    /// let reversed = repository.intern_reversed_domain("www.example.com.");
+    /// assert_eq!(reversed, "com.example.www");
+    /// assert_eq!(reversed, repository.intern_reversed_domain("www.example.com"));
+    /// assert_eq!(repository.intern_reversed_domain("127.0.0.1"), "127.0.0.1");
+    /// ```
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn intern_reversed_domain(&self, domain: &str) -> ScopedSto<'_> {
+        if domain.parse::<IpAddr>().is_ok() {
+            return ScopedSto::intern_in(domain, self);
+        }
+
+        let domain = domain.strip_suffix('.').unwrap_or(domain);
+        with_scratch(|buf| {
+            for (i, label) in domain.rsplit('.').enumerate() {
+                if i > 0 {
+                    buf.push('.');
+                }
+                buf.push_str(label);
+            }
+            ScopedSto::intern_in(&*buf, self)
+        })
+    }
+
+    /// Returns the interned string of a dense id.
+    ///
+    /// Ids are only assigned by a `Repository` built with [RepositoryBuilder::indexed],
+    /// otherwise this always returns `None`.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::builder().indexed().build();
+    /// let hello = ScopedSto::intern_in("hello", &repository);
+    /// assert_eq!(repository.resolve(0), Some(hello));
+    /// assert_eq!(repository.resolve(1), None);
+    /// ```
+    pub fn resolve(&self, id: u32) -> Option<ScopedSto<'_>> {
+        self.ids
+            .lock()
+            .get(id as usize)
+            .copied()
+            .map(ScopedSto::new)
+    }
+
+    /// Exports the interned strings with their dense ids, sorted by id.
+    ///
+    /// Ids are only assigned by a `Repository` built with [RepositoryBuilder::indexed],
+    /// otherwise the table is empty.
+    /// Ids are stable only within the lifetime of this `Repository`, see [RepositoryBuilder::indexed].
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::builder().indexed().build();
+    /// ScopedSto::intern_in("hello", &repository);
+    /// ScopedSto::intern_in("world", &repository);
+    /// ScopedSto::intern_in("hello", &repository);
+    /// assert_eq!(repository.export_table(), vec![(0, "hello"), (1, "world")]);
+    /// ```
+    pub fn export_table(&self) -> Vec<(u32, &str)> {
+        self.ids
+            .lock()
+            .iter()
+            .enumerate()
+            .map(|(id, entry)| (id as u32, entry.as_str()))
+            .collect()
+    }
+
+    /// Returns a closure which interns strings into this [Repository],
+    /// so interning composes with iterator adapters.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// let words = "to be or not to be"
+    ///     .split(' ')
+    ///     .map(repository.interner())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(words[0], words[4]);
+    /// assert_eq!(words[1], ScopedSto::intern_in("be", &repository));
+    /// ```
+    pub fn interner<'a>(&'a self) -> impl FnMut(&str) -> ScopedSto<'a> + 'a {
+        move |string| ScopedSto::new(self.get_or_insert(string))
+    }
+
+    /// Returns the time spent in each phase of interning so far.
+    ///
+    /// Measuring adds overhead to every interning, so the feature `debug-timing`
+    /// is only intended for profiling.
+    #[cfg(feature = "debug-timing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "debug-timing")))]
+    pub fn timing_report(&self) -> TimingReport {
+        self.timing.report()
+    }
+
+    /// Collects all interned strings, sorted by their contents.
+    ///
+    /// This walks every bucket once and then sorts the collected handles,
+    /// so it costs O(n log n) each time it is called.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// ScopedSto::intern_in("world", &repository);
+    /// ScopedSto::intern_in("hello", &repository);
+    ///
+    /// let sorted = repository.to_sorted_vec();
+    /// assert_eq!(sorted, ["hello", "world"]);
+    /// ```
+    pub fn to_sorted_vec(&self) -> Vec<ScopedSto<'_>> {
+        let mut handles = Vec::new();
+        self.for_each_entry(|entry| handles.push(ScopedSto::new(entry)));
+        handles.sort_unstable();
+        handles
+    }
+
+    /// Copies all interned strings into owned `String`s, in no particular order,
+    /// and drops the [Repository] with its arena.
+    ///
+    /// Unlike [Repository::iter], the strings outlive the `Repository`,
+    /// e.g. to be persisted after it is torn down. Interned bytes are not included.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// ScopedSto::intern_in("world", &repository);
+    /// ScopedSto::intern_in("hello", &repository);
+    ///
+    /// let mut strings = repository.into_strings();
+    /// strings.sort_unstable();
+    /// assert_eq!(strings, ["hello", "world"]);
+    /// ```
+    pub fn into_strings(self) -> Vec<String> {
+        let mut strings = Vec::with_capacity(self.len());
+        self.for_each_entry(|entry| strings.push(String::from(entry.as_str())));
+        strings
     }
 }
 
-impl Repository {
-    pub(crate) fn get_or_insert(&self, string: &str) -> Entry {
-        let hash = Self::get_hash(string);
+impl<const BUCKETS: usize, H: BuildHasher> Repository<BUCKETS, H> {
+    /// Looks up an interned string without inserting it.
+    pub(crate) fn find(&self, string: &str) -> Option<Entry> {
+        let hash = self.hash_of(string);
         self.buckets[Self::determine_bucket(hash)]
             .0
-            .lock()
-            .get_or_insert(hash, string)
+            .read()
+            .entries
+            .get(hash, string.as_bytes())
+    }
+
+    pub(crate) fn get_or_insert(&self, string: &str) -> Entry {
+        self.lock_and_insert(string).entry
+    }
+
+    /// Interns a string copied in bulk, where no handle is returned,
+    /// and returns whether it is newly interned.
+    ///
+    /// With the feature `refcount`, an interned string keeps its reference count, so it is
+    /// removed once its handles are released. A new string holds a single reference instead,
+    /// which keeps it interned until [Repository::retain] or [Repository::collect].
+    fn copy_in(&self, string: &str) -> bool {
+        let mut stopwatch = Stopwatch::start();
+        let hash = self.hash_of(string);
+        let hash_nanos = stopwatch.lap();
+        let mut bucket = self.buckets[Self::determine_bucket(hash)].0.lock();
+        #[cfg(feature = "refcount")]
+        if bucket.entries.get(hash, string.as_bytes()).is_some() {
+            return false;
+        }
+        self.try_insert_locked(&mut bucket, hash, hash_nanos, string, |dest| unsafe {
+            copy_nonoverlapping(string.as_ptr(), dest, string.len())
+        })
+        .unwrap_or_else(|err| panic!("{err}"))
+        .inserted
+    }
+
+    /// Interns a string and returns the outcome.
+    ///
+    /// All interning goes through here, so it is where the timing is recorded.
+    #[inline]
+    fn lock_and_insert(&self, string: &str) -> Outcome {
+        self.lock_and_insert_with(string, |dest| unsafe {
+            copy_nonoverlapping(string.as_ptr(), dest, string.len())
+        })
+    }
+
+    /// Same as [Repository::lock_and_insert], but on a miss, `fill` is called
+    /// to write the `string.len()` bytes of `string` to the given destination.
+    #[inline]
+    fn lock_and_insert_with<F>(&self, string: &str, fill: F) -> Outcome
+    where
+        F: FnOnce(*mut u8),
+    {
+        let mut stopwatch = Stopwatch::start();
+        let hash = self.hash_of(string);
+        let hash_nanos = stopwatch.lap();
+        self.lock_and_insert_hashed(hash, hash_nanos, string, fill)
+    }
+
+    /// Same as [Repository::lock_and_insert_with], but with the precomputed `hash` of `string`,
+    /// which took `hash_nanos` to compute.
+    #[inline]
+    fn lock_and_insert_hashed<F>(
+        &self,
+        hash: u64,
+        hash_nanos: u64,
+        string: &str,
+        fill: F,
+    ) -> Outcome
+    where
+        F: FnOnce(*mut u8),
+    {
+        self.try_lock_and_insert_hashed(hash, hash_nanos, string, fill)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Same as [Repository::lock_and_insert_hashed], but returns an error
+    /// instead of panicking if the string can not be allocated.
+    #[inline]
+    fn try_lock_and_insert_hashed<F>(
+        &self,
+        hash: u64,
+        hash_nanos: u64,
+        string: &str,
+        fill: F,
+    ) -> Result<Outcome, InternError>
+    where
+        F: FnOnce(*mut u8),
+    {
+        let bucket = &self.buckets[Self::determine_bucket(hash)].0;
+
+        // most strings are interned already in read-heavy workloads,
+        // so look them up without blocking other readers first
+        #[cfg(feature = "rwlock")]
+        {
+            let mut stopwatch = Stopwatch::start();
+            let bucket = bucket.read();
+            if let Some(entry) = bucket.entries.get(hash, string.as_bytes()) {
+                // the entry is live, and can not be released while the bucket is locked
+                #[cfg(feature = "refcount")]
+                entry.refcount().fetch_add(1, AtomicOrdering::Relaxed);
+                self.timing.record(hash_nanos, stopwatch.lap(), 0);
+                return Ok(Outcome {
+                    entry,
+                    grew: false,
+                    inserted: false,
+                });
+            }
+        }
+
+        self.try_insert_locked(&mut bucket.lock(), hash, hash_nanos, string, fill)
+    }
+
+    /// Same as [Repository::try_lock_and_insert_hashed], but into the bucket of `hash`
+    /// which is already locked.
+    #[inline]
+    fn try_insert_locked<F>(
+        &self,
+        bucket: &mut BucketImpl,
+        hash: u64,
+        hash_nanos: u64,
+        string: &str,
+        fill: F,
+    ) -> Result<Outcome, InternError>
+    where
+        F: FnOnce(*mut u8),
+    {
+        self.try_insert_locked_by(bucket, hash, hash_nanos, string, |arena| {
+            let ptr = arena.try_alloc(hash, string.len(), &self.config)?;
+            fill(Arena::chars_of(ptr));
+            Ok(ptr)
+        })
+    }
+
+    /// Same as [Repository::try_insert_locked], but on a miss, `alloc` allocates the entry
+    /// in the arena of the bucket and writes it.
+    #[inline]
+    fn try_insert_locked_by<A>(
+        &self,
+        bucket: &mut BucketImpl,
+        hash: u64,
+        hash_nanos: u64,
+        string: &str,
+        alloc: A,
+    ) -> Result<Outcome, InternError>
+    where
+        A: FnOnce(&mut Arena) -> Result<NonNull<u8>, InternError>,
+    {
+        let mut stopwatch = Stopwatch::start();
+        let mut alloc_nanos = 0;
+        let BucketImpl { arena, entries } = bucket;
+        #[cfg(feature = "refcount")]
+        for entry in entries.remove_dead_if_full() {
+            unsafe { arena.recycle(entry) };
+        }
+        let outcome = entries.try_get_or_insert(hash, string.as_bytes(), &self.config, || {
+            let mut stopwatch = Stopwatch::start();
+            let ptr = alloc(arena)?;
+            alloc_nanos += stopwatch.lap();
+            if self.config.indexed {
+                let mut ids = self.ids.lock();
+                assert!(ids.len() <= u32::MAX as usize, "too many ids");
+                unsafe { Entry(ptr).set_id(ids.len() as u32) };
+                ids.push(Entry(ptr));
+            }
+            Ok(Entry(ptr))
+        })?;
+        let probe_nanos = stopwatch.lap() - alloc_nanos;
+
+        self.timing.record(hash_nanos, probe_nanos, alloc_nanos);
+        Ok(outcome)
+    }
+
+    /// Decrements the reference count of `entry`, and marks it to be removed if it drops to 0,
+    /// unless ids are assigned.
+    ///
+    /// # Safety
+    /// `entry` must be interned in this [Repository] with a positive reference count.
+    #[cfg(feature = "refcount")]
+    pub(crate) unsafe fn release(&self, entry: Entry) -> bool {
+        let mut bucket = self.buckets[Self::determine_bucket(entry.hash())].0.lock();
+        let refcount = entry.refcount().load(AtomicOrdering::Relaxed);
+        assert!(
+            refcount > 0,
+            "a string is released more times than interned"
+        );
+        entry
+            .refcount()
+            .store(refcount - 1, AtomicOrdering::Relaxed);
+        let dead = refcount == 1 && !self.config.indexed;
+        if dead {
+            bucket.entries.mark_dead();
+        }
+        dead
     }
 }
 
-impl Repository {
-    fn get_hash(string: &str) -> u64 {
-        static RANDOM: RandomState =
-            RandomState::with_seeds(0x01230456, 0x04560789, 0x07890123, 0x02580137);
-        let mut hasher = RANDOM.build_hasher();
-        hasher.write(string.as_bytes());
-        hasher.finish()
+impl<const BUCKETS: usize, H: BuildHasher> Repository<BUCKETS, H> {
+    /// Calls `f` on every interned entry, locking one bucket at a time.
+    fn for_each_entry(&self, mut f: impl FnMut(Entry)) {
+        for bucket in &self.buckets {
+            bucket.0.read().entries.iter().for_each(&mut f);
+        }
     }
 
+    pub(crate) fn hash_bytes(&self, bytes: &[u8]) -> u64 {
+        hash_with(&self.hasher, bytes)
+    }
+
+    /// Picks a bucket by the highest bits of `hash`,
+    /// while the lowest bits pick a slot in the hash table of the bucket.
     const fn determine_bucket(hash: u64) -> usize {
-        (hash >> BUCKET_RSHIFT) as usize
+        if BUCKETS == 1 {
+            // shifting by the full width would overflow
+            0
+        } else {
+            (hash >> (u64::BITS - BUCKETS.trailing_zeros())) as usize
+        }
     }
 }
 
+/// The hasher of [Repository::new].
+pub(crate) const fn default_hash_builder() -> RandomState {
+    RandomState::with_seeds(0x01230456, 0x04560789, 0x07890123, 0x02580137)
+}
+
+pub(crate) fn hash_with<H: BuildHasher>(hash_builder: &H, bytes: &[u8]) -> u64 {
+    let mut hasher = hash_builder.build_hasher();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
 impl Default for Repository {
     /// See [Repository::new].
     fn default() -> Self {
@@ -93,7 +2052,56 @@ impl Default for Repository {
     }
 }
 
-impl fmt::Debug for Repository {
+/// Constructs a new [Repository] and interns all strings into it.
+///
+/// ## Example
+/// ```
+/// # use sto::Repository;
+/// let repository: Repository = "a b a c".split(' ').collect();
+/// assert_eq!(repository.len(), 3);
+/// ```
+impl<S: AsRef<str>> FromIterator<S> for Repository {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        let repository = Self::new();
+        (&repository).extend(iter);
+        repository
+    }
+}
+
+/// Interns all strings, the same as extending a `&Repository`.
+impl<S: AsRef<str>, const BUCKETS: usize, H: BuildHasher> Extend<S> for Repository<BUCKETS, H> {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, iter: I) {
+        (&*self).extend(iter)
+    }
+}
+
+/// Interns all strings through a shared reference, since interning does not need `&mut`.
+///
+/// ## Example
+/// ```
+/// # use sto::Repository;
+/// let repository = Repository::new();
+/// (&repository).extend(["hello", "world"]);
+/// assert!(repository.contains("hello"));
+/// ```
+impl<S: AsRef<str>, const BUCKETS: usize, H: BuildHasher> Extend<S> for &Repository<BUCKETS, H> {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, iter: I) {
+        for string in iter {
+            self.copy_in(string.as_ref());
+        }
+    }
+}
+
+impl<'a, const BUCKETS: usize, H: BuildHasher> IntoIterator for &'a Repository<BUCKETS, H> {
+    type Item = ScopedSto<'a>;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<const BUCKETS: usize, H> fmt::Debug for Repository<BUCKETS, H> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_struct("Repository").finish()
     }
@@ -101,19 +2109,92 @@ impl fmt::Debug for Repository {
 
 #[repr(align(32))]
 #[derive(Default)]
-struct Bucket(Mutex<BucketImpl>);
+pub(crate) struct Bucket(pub(crate) BucketLock<BucketImpl>);
+
+impl Bucket {
+    pub(crate) fn new(config: &Config) -> Self {
+        Self(BucketLock::new(BucketImpl::new(config)))
+    }
+}
 
-/// BucketImpl has 32 bytes on 64 bit hardware
+/// BucketImpl has 48 bytes on 64 bit hardware
 #[derive(Default)]
-struct BucketImpl {
-    arena: Arena,
-    entries: Entries,
+pub(crate) struct BucketImpl {
+    pub(crate) arena: Arena,
+    pub(crate) entries: Entries,
 }
 
 impl BucketImpl {
+    pub(crate) fn new(config: &Config) -> Self {
+        Self {
+            arena: Arena::default(),
+            entries: Entries::new(config.load_factor),
+        }
+    }
+
+    fn clear(&mut self, config: &Config) {
+        self.entries = Entries::new(config.load_factor);
+        self.arena.clear();
+    }
+
+    pub(crate) fn allocated_memory(&self) -> usize {
+        self.entries.allocated_memory() + self.arena.allocated_memory()
+    }
+
     #[inline]
-    fn get_or_insert(&mut self, hash: u64, string: &str) -> Entry {
-        self.entries
-            .get_or_insert(hash, string, || Entry(self.arena.alloc_str(hash, string)))
+    pub(crate) fn get_or_insert(&mut self, hash: u64, string: &[u8], config: &Config) -> Outcome {
+        let BucketImpl { arena, entries } = self;
+        entries
+            .try_get_or_insert(hash, string, config, || {
+                let ptr = arena.try_alloc(hash, string.len(), config)?;
+                unsafe { copy_nonoverlapping(string.as_ptr(), Arena::chars_of(ptr), string.len()) };
+                Ok(Entry(ptr))
+            })
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+}
+
+/// An iterator over the interned strings of a [Repository].
+///
+/// See [Repository::iter].
+pub struct Iter<'a> {
+    buckets: core::slice::Iter<'a, Bucket>,
+    /// the bucket being iterated
+    guard: Option<ReadGuard<'a, BucketImpl>>,
+    /// the next slot to visit in the bucket being iterated
+    pos: usize,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = ScopedSto<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(guard) = &self.guard {
+                let slots = guard.entries.slots();
+                while let Some(slot) = slots.get(self.pos) {
+                    self.pos += 1;
+                    if let Some(entry) = slot {
+                        #[cfg(feature = "refcount")]
+                        if !entry.is_live() {
+                            continue;
+                        }
+                        return Some(ScopedSto::new(*entry));
+                    }
+                }
+            }
+            // release the current bucket before locking the next one
+            self.guard = None;
+            self.guard = Some(self.buckets.next()?.0.read());
+            self.pos = 0;
+        }
+    }
+}
+
+impl FusedIterator for Iter<'_> {}
+
+impl fmt::Debug for Iter<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Iter").finish()
     }
 }
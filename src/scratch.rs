@@ -0,0 +1,29 @@
+use alloc::string::String;
+#[cfg(feature = "std")]
+use core::cell::RefCell;
+
+#[cfg(feature = "std")]
+thread_local! {
+    static SCRATCH: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Runs `f` with a cleared thread-local buffer, so building a temporary string
+/// does not allocate on every call.
+///
+/// If the buffer is already in use (e.g. `f` is re-entered), a fresh `String` is used instead.
+#[cfg(feature = "std")]
+pub(crate) fn with_scratch<R>(f: impl FnOnce(&mut String) -> R) -> R {
+    SCRATCH.with(|scratch| match scratch.try_borrow_mut() {
+        Ok(mut buf) => {
+            buf.clear();
+            f(&mut buf)
+        }
+        Err(_) => f(&mut String::new()),
+    })
+}
+
+/// Runs `f` with a fresh `String`, since there is no thread-local storage without `std`.
+#[cfg(not(feature = "std"))]
+pub(crate) fn with_scratch<R>(f: impl FnOnce(&mut String) -> R) -> R {
+    f(&mut String::new())
+}
@@ -0,0 +1,126 @@
+#[cfg(feature = "global")]
+use crate::Sto;
+use crate::{Repository, ScopedSto};
+use ahash::RandomState;
+use core::fmt;
+use core::hash::BuildHasher;
+#[cfg(feature = "global")]
+use serde::de::Deserialize;
+use serde::de::{DeserializeSeed, Deserializer, Error, Visitor};
+use serde::{Serialize, Serializer};
+
+/// Serializes the interned string.
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl Serialize for ScopedSto<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Deserializes a string and interns it in the global [Repository].
+///
+/// There is no global `Repository` without the feature `global`,
+/// so a [ScopedSto] with a shorter lifetime can not be deserialized this way.
+///
+/// ## Example
+/// ```
+/// # use sto::Sto;
+/// let keys: Vec<Sto> = serde_json::from_str(r#"["hello", "world", "hello"]"#).unwrap();
+/// assert_eq!(keys[0], keys[2]);
+/// assert_eq!(serde_json::to_string(&keys).unwrap(), r#"["hello","world","hello"]"#);
+/// ```
+#[cfg(feature = "global")]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "serde", feature = "global"))))]
+impl<'de> Deserialize<'de> for Sto {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct StoVisitor;
+
+        impl<'de> Visitor<'de> for StoVisitor {
+            type Value = Sto;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a string")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: Error,
+            {
+                Ok(Sto::from(v))
+            }
+        }
+
+        deserializer.deserialize_str(StoVisitor)
+    }
+}
+
+/// A [DeserializeSeed] which deserializes a string and interns it in the wrapped [Repository].
+///
+/// Unlike the `Deserialize` implementation of `Sto`, it does not need the global
+/// `Repository`, so the strings of a document can be interned in a scoped `Repository`.
+/// Both borrowed and owned strings are accepted.
+///
+/// ## Example
+/// ```
+/// # use sto::{Repository, RepositorySeed};
+/// use serde::de::DeserializeSeed;
+///
+/// let repository = Repository::new();
+/// let mut deserializer = serde_json::Deserializer::from_str(r#""hello""#);
+/// let hello = RepositorySeed(&repository).deserialize(&mut deserializer).unwrap();
+/// assert_eq!(hello, "hello");
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub struct RepositorySeed<'a, const BUCKETS: usize = 64, H = RandomState>(
+    pub &'a Repository<BUCKETS, H>,
+);
+
+impl<const BUCKETS: usize, H> Copy for RepositorySeed<'_, BUCKETS, H> {}
+
+impl<const BUCKETS: usize, H> Clone for RepositorySeed<'_, BUCKETS, H> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<const BUCKETS: usize, H> fmt::Debug for RepositorySeed<'_, BUCKETS, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RepositorySeed").field(self.0).finish()
+    }
+}
+
+impl<'de, 'a, const BUCKETS: usize, H: BuildHasher> DeserializeSeed<'de>
+    for RepositorySeed<'a, BUCKETS, H>
+{
+    type Value = ScopedSto<'a>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de, 'a, const BUCKETS: usize, H: BuildHasher> Visitor<'de>
+    for RepositorySeed<'a, BUCKETS, H>
+{
+    type Value = ScopedSto<'a>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        Ok(ScopedSto::intern_in(v, self.0))
+    }
+}
@@ -0,0 +1,102 @@
+use crate::constants::BUCKET_NUMBER;
+use crate::Repository;
+use alloc::vec::Vec;
+use core::hash::BuildHasher;
+use std::io::{self, Read, Write};
+
+/// | magic (4) | buckets (u32) | fingerprint (u64) | count (u64) | entries |
+///
+/// where every entry is | hash (u64) | len (u64) | bytes (len) |, all integers in little endian.
+const MAGIC: &[u8; 4] = b"sto\x01";
+
+/// The string whose hash identifies the hasher of a snapshot.
+const FINGERPRINT_PROBE: &str = "sto snapshot fingerprint";
+
+impl<const BUCKETS: usize, H: BuildHasher> Repository<BUCKETS, H> {
+    /// Writes all interned strings with their hashes to `w`,
+    /// so they can be restored by [Repository::load_from].
+    ///
+    /// The snapshot records the number of buckets and a fingerprint of the hasher,
+    /// and interned bytes are not included.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sto::{Repository, ScopedSto};
+    /// let repository = Repository::new();
+    /// ScopedSto::intern_in("hello", &repository);
+    ///
+    /// let mut snapshot = Vec::new();
+    /// repository.save_to(&mut snapshot).unwrap();
+    /// let restored = Repository::load_from(&mut snapshot.as_slice()).unwrap();
+    /// assert_eq!(restored.to_sorted_vec(), ["hello"]);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn save_to(&self, w: &mut impl Write) -> io::Result<()> {
+        let strings = self.iter().collect::<Vec<_>>();
+        w.write_all(MAGIC)?;
+        w.write_all(&(BUCKETS as u32).to_le_bytes())?;
+        w.write_all(&self.hash_of(FINGERPRINT_PROBE).to_le_bytes())?;
+        w.write_all(&(strings.len() as u64).to_le_bytes())?;
+        for sto in strings {
+            w.write_all(&sto.hash().to_le_bytes())?;
+            w.write_all(&(sto.len() as u64).to_le_bytes())?;
+            w.write_all(sto.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+impl Repository {
+    /// Restores a [Repository] from a snapshot written by [Repository::save_to].
+    ///
+    /// The snapshot must be written by a `Repository` with the same number of buckets
+    /// and the same hasher as [Repository::new], otherwise an error of
+    /// [io::ErrorKind::InvalidData] is returned, as well as for a malformed snapshot.
+    /// The snapshot is not trusted, so every stored hash is checked against its string.
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn load_from(r: &mut impl Read) -> io::Result<Repository> {
+        let repository = Repository::new();
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a snapshot of a repository"));
+        }
+        let mut buckets = [0; 4];
+        r.read_exact(&mut buckets)?;
+        if u32::from_le_bytes(buckets) as usize != BUCKET_NUMBER {
+            return Err(invalid_data("the snapshot has another number of buckets"));
+        }
+        if read_u64(r)? != repository.hash_of(FINGERPRINT_PROBE) {
+            return Err(invalid_data("the snapshot is hashed by another hasher"));
+        }
+
+        let count = read_u64(r)?;
+        let mut buf = Vec::new();
+        for _ in 0..count {
+            let hash = read_u64(r)?;
+            let len = read_u64(r)?;
+            buf.clear();
+            // the length is not trusted to preallocate the buffer
+            if r.take(len).read_to_end(&mut buf)? as u64 != len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let string = core::str::from_utf8(&buf)
+                .map_err(|_| invalid_data("the snapshot has an invalid UTF-8 string"))?;
+            if repository.hash_of(string) != hash {
+                return Err(invalid_data("the snapshot has a corrupted hash"));
+            }
+            repository.intern_with_hash(hash, string);
+        }
+        Ok(repository)
+    }
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
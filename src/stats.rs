@@ -0,0 +1,43 @@
+/// A breakdown of the memory allocated by a [Repository](crate::Repository),
+/// see [Repository::memory_stats](crate::Repository::memory_stats).
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct MemoryStats {
+    /// Bytes of arena chunks where interned strings are stored.
+    pub arena_bytes: usize,
+    /// Bytes of hash tables indexing interned strings.
+    pub entries_bytes: usize,
+    /// The number of arena chunks.
+    pub chunk_count: usize,
+    /// The number of interned strings and bytes.
+    pub entry_count: usize,
+    /// Bytes of the largest arena chunk.
+    ///
+    /// A chunk larger than the default capacity is allocated for every large string.
+    pub largest_chunk_bytes: usize,
+    /// Bytes of arena chunks not taken by the hashes, lengths and contents of interned strings.
+    ///
+    /// They include alignment paddings, sentinels, chunk headers,
+    /// and the tails of chunks which are left unused.
+    pub wasted_bytes: usize,
+}
+
+impl MemoryStats {
+    /// The total allocated bytes, the same as [Repository::allocated_memory](crate::Repository::allocated_memory).
+    pub fn total_bytes(&self) -> usize {
+        self.arena_bytes + self.entries_bytes
+    }
+}
+
+/// The statistics of a single bucket of a [Repository](crate::Repository),
+/// see [Repository::bucket_stats](crate::Repository::bucket_stats).
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct BucketStat {
+    /// The index of the bucket, picked by the highest bits of hashes.
+    pub index: usize,
+    /// The number of strings interned in the bucket.
+    pub entry_count: usize,
+    /// The number of slots of the hash table of the bucket, or 0 if it is not allocated.
+    pub capacity: usize,
+    /// Bytes of arena chunks of the bucket.
+    pub arena_bytes: usize,
+}
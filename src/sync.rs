@@ -0,0 +1,93 @@
+//! The locks of buckets.
+//!
+//! Buckets are locked by `parking_lot` with the feature `std`, otherwise by spin locks
+//! of the feature `spin`.
+//! With the feature `rwlock`, they are read-write locks, so lookups of interned strings
+//! do not block each other, while insertions are still exclusive.
+
+#[cfg(feature = "std")]
+pub(crate) use parking_lot::Mutex;
+#[cfg(not(feature = "std"))]
+pub(crate) use spin::Mutex;
+
+#[cfg(all(not(feature = "rwlock"), feature = "std"))]
+use parking_lot::MutexGuard;
+#[cfg(all(not(feature = "rwlock"), not(feature = "std")))]
+use spin::MutexGuard;
+
+#[cfg(all(feature = "rwlock", feature = "std"))]
+use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+#[cfg(all(feature = "rwlock", not(feature = "std")))]
+use spin::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// The guard of an exclusive lock.
+#[cfg(not(feature = "rwlock"))]
+pub(crate) type WriteGuard<'a, T> = MutexGuard<'a, T>;
+/// The guard of a shared lock, which is exclusive too without the feature `rwlock`.
+#[cfg(not(feature = "rwlock"))]
+pub(crate) type ReadGuard<'a, T> = MutexGuard<'a, T>;
+/// The guard of an exclusive lock.
+#[cfg(feature = "rwlock")]
+pub(crate) type WriteGuard<'a, T> = RwLockWriteGuard<'a, T>;
+/// The guard of a shared lock.
+#[cfg(feature = "rwlock")]
+pub(crate) type ReadGuard<'a, T> = RwLockReadGuard<'a, T>;
+
+/// A mutex, or a read-write lock with the feature `rwlock`.
+#[derive(Default)]
+pub(crate) struct BucketLock<T> {
+    #[cfg(not(feature = "rwlock"))]
+    inner: Mutex<T>,
+    #[cfg(feature = "rwlock")]
+    inner: RwLock<T>,
+}
+
+impl<T> BucketLock<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Self {
+            #[cfg(not(feature = "rwlock"))]
+            inner: Mutex::new(value),
+            #[cfg(feature = "rwlock")]
+            inner: RwLock::new(value),
+        }
+    }
+
+    /// Locks exclusively, to modify the value.
+    #[inline]
+    pub(crate) fn lock(&self) -> WriteGuard<'_, T> {
+        #[cfg(not(feature = "rwlock"))]
+        return self.inner.lock();
+        #[cfg(feature = "rwlock")]
+        return self.inner.write();
+    }
+
+    /// Locks to read the value, which may be shared with other readers.
+    #[inline]
+    pub(crate) fn read(&self) -> ReadGuard<'_, T> {
+        #[cfg(not(feature = "rwlock"))]
+        return self.inner.lock();
+        #[cfg(feature = "rwlock")]
+        return self.inner.read();
+    }
+
+    /// Same as [BucketLock::lock], but returns `None` instead of blocking if it is locked.
+    #[inline]
+    pub(crate) fn try_lock(&self) -> Option<WriteGuard<'_, T>> {
+        #[cfg(not(feature = "rwlock"))]
+        return self.inner.try_lock();
+        #[cfg(feature = "rwlock")]
+        return self.inner.try_write();
+    }
+
+    /// Same as [BucketLock::read], but returns `None` instead of blocking if it is locked.
+    #[cfg(feature = "rwlock")]
+    #[inline]
+    pub(crate) fn try_read(&self) -> Option<ReadGuard<'_, T>> {
+        self.inner.try_read()
+    }
+
+    #[inline]
+    pub(crate) fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+}
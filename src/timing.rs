@@ -0,0 +1,91 @@
+//! Timing hooks of the feature `debug-timing`.
+//!
+//! Without the feature, all types here are zero-sized and all methods are no-ops.
+
+#[cfg(feature = "debug-timing")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "debug-timing")]
+use std::time::Instant;
+
+/// Measures the nanoseconds between laps.
+pub(crate) struct Stopwatch {
+    #[cfg(feature = "debug-timing")]
+    last: Instant,
+}
+
+impl Stopwatch {
+    #[inline(always)]
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(feature = "debug-timing")]
+            last: Instant::now(),
+        }
+    }
+
+    /// Returns the nanoseconds elapsed since the previous lap.
+    #[inline(always)]
+    pub(crate) fn lap(&mut self) -> u64 {
+        #[cfg(feature = "debug-timing")]
+        {
+            let now = Instant::now();
+            let elapsed = now.duration_since(self.last).as_nanos() as u64;
+            self.last = now;
+            elapsed
+        }
+        #[cfg(not(feature = "debug-timing"))]
+        0
+    }
+}
+
+/// Aggregated timing of a [Repository](crate::Repository).
+#[derive(Default)]
+pub(crate) struct Timing {
+    #[cfg(feature = "debug-timing")]
+    calls: AtomicU64,
+    #[cfg(feature = "debug-timing")]
+    hash_nanos: AtomicU64,
+    #[cfg(feature = "debug-timing")]
+    probe_nanos: AtomicU64,
+    #[cfg(feature = "debug-timing")]
+    alloc_nanos: AtomicU64,
+}
+
+impl Timing {
+    #[inline(always)]
+    #[allow(unused_variables)]
+    pub(crate) fn record(&self, hash_nanos: u64, probe_nanos: u64, alloc_nanos: u64) {
+        #[cfg(feature = "debug-timing")]
+        {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            self.hash_nanos.fetch_add(hash_nanos, Ordering::Relaxed);
+            self.probe_nanos.fetch_add(probe_nanos, Ordering::Relaxed);
+            self.alloc_nanos.fetch_add(alloc_nanos, Ordering::Relaxed);
+        }
+    }
+
+    #[cfg(feature = "debug-timing")]
+    pub(crate) fn report(&self) -> TimingReport {
+        TimingReport {
+            calls: self.calls.load(Ordering::Relaxed),
+            hash_nanos: self.hash_nanos.load(Ordering::Relaxed),
+            probe_nanos: self.probe_nanos.load(Ordering::Relaxed),
+            alloc_nanos: self.alloc_nanos.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Time spent in each phase of interning, see [Repository::timing_report](crate::Repository::timing_report).
+#[cfg(feature = "debug-timing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "debug-timing")))]
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct TimingReport {
+    /// The number of interning calls.
+    pub calls: u64,
+    /// Nanoseconds spent in hashing strings.
+    pub hash_nanos: u64,
+    /// Nanoseconds spent in probing the hash tables, including their growth,
+    /// but excluding waiting for locks.
+    pub probe_nanos: u64,
+    /// Nanoseconds spent in allocating and copying strings into the arenas.
+    pub alloc_nanos: u64,
+}